@@ -0,0 +1,137 @@
+use num::PrimInt;
+
+/// Get the average of `lo` and `hi` rounded down, without overflowing.
+#[inline]
+pub fn midpoint<I: PrimInt>(lo: I, hi: I) -> I {
+    (lo & hi) + ((lo ^ hi) >> 1)
+}
+
+/// Find the first value in `lo..hi` where the predicate holds. The predicate must be false up to
+/// some point and true after it. It returns `None` if it's false for the whole range.
+pub fn lower_bound<I, F>(mut lo: I, hi: I, pred: F) -> Option<I> where I: PrimInt, F: Fn(I) -> bool {
+    let end = hi;
+    let mut hi = hi;
+    while lo < hi {
+        let mid = midpoint(lo, hi);
+        if pred(mid) {
+            hi = mid;
+        } else {
+            lo = mid + I::one();
+        }
+    }
+
+    if lo < end { Some(lo) } else { None }
+}
+
+/// Find the last value in `lo..hi` where the predicate holds. The predicate must be true up to
+/// some point and false after it. It returns `None` if it's false for the whole range.
+pub fn upper_bound<I, F>(lo: I, hi: I, pred: F) -> Option<I> where I: PrimInt, F: Fn(I) -> bool {
+    match lower_bound(lo, hi, |v| !pred(v)) {
+        Some(first_false) if first_false == lo => None,
+        Some(first_false) => Some(first_false - I::one()),
+        None if lo < hi => Some(hi - I::one()),
+        None => None,
+    }
+}
+
+/// Find the first value from `start` and up where the predicate holds, without knowing how far
+/// up it is. The steps double until it holds, and then it's bisected between the last two. The
+/// predicate must be false up to some point and true after it.
+pub fn gallop<I, F>(start: I, pred: F) -> Option<I> where I: PrimInt, F: Fn(I) -> bool {
+    if pred(start) {
+        return Some(start);
+    }
+
+    let mut low = start;
+    let mut step = I::one();
+    loop {
+        let high = low.checked_add(&step).unwrap_or_else(I::max_value);
+        if pred(high) {
+            return lower_bound(low + I::one(), high, &pred).or(Some(high));
+        }
+        if high == I::max_value() {
+            return None;
+        }
+
+        low = high;
+        step = step.checked_add(&step).unwrap_or_else(I::max_value);
+    }
+}
+
+/// Find the value in `lo..hi` where `f` is the highest, and that value. `f` must go strictly up
+/// and then strictly down, though it may stay flat at the top. It bisects on the slope, which is
+/// how ternary search is done over integers.
+pub fn ternary_max<I, V, F>(lo: I, hi: I, f: F) -> Option<(I, V)> where I: PrimInt, V: Ord, F: Fn(I) -> V {
+    if lo >= hi {
+        return None;
+    }
+
+    let top = lower_bound(lo, hi - I::one(), |v| f(v) >= f(v + I::one())).unwrap_or(hi - I::one());
+    Some((top, f(top)))
+}
+
+/// Find the value in `lo..hi` where `f` is the lowest, like `ternary_max`.
+pub fn ternary_min<I, V, F>(lo: I, hi: I, f: F) -> Option<(I, V)> where I: PrimInt, V: Ord, F: Fn(I) -> V {
+    if lo >= hi {
+        return None;
+    }
+
+    let bottom = lower_bound(lo, hi - I::one(), |v| f(v) <= f(v + I::one())).unwrap_or(hi - I::one());
+    Some((bottom, f(bottom)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_match_linear_scans() {
+        for lo in i8::MIN..=i8::MAX {
+            for hi in (lo..=i8::MAX).step_by(3) {
+                for t in (i8::MIN..=i8::MAX).step_by(5).chain([lo, hi, i8::MAX]) {
+                    assert_eq!(lower_bound(lo, hi, |v| v >= t), (lo..hi).find(|v| *v >= t));
+                    assert_eq!(upper_bound(lo, hi, |v| v <= t), (lo..hi).rev().find(|v| *v <= t));
+                }
+            }
+
+            assert_eq!(gallop(lo, |v| v >= 100), (lo..=i8::MAX).find(|v| *v >= 100));
+            assert_eq!(gallop(lo, |_| false), None);
+        }
+
+        for a in 0..=255u8 {
+            for b in a..=255u8 {
+                assert_eq!(midpoint(a, b) as u32, (a as u32 + b as u32) / 2);
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_do_not_overflow() {
+        assert_eq!(midpoint(i64::MIN, i64::MAX), -1);
+        assert_eq!(midpoint(u64::MAX - 1, u64::MAX), u64::MAX - 1);
+        assert_eq!(lower_bound(i64::MIN, i64::MAX, |v| v >= 1 << 40), Some(1 << 40));
+        assert_eq!(lower_bound(i64::MIN, i64::MAX, |v| v >= i64::MIN), Some(i64::MIN));
+        assert_eq!(upper_bound(0, u64::MAX, |v| v < u64::MAX - 5), Some(u64::MAX - 6));
+        assert_eq!(gallop(0u64, |v| v >= u64::MAX), Some(u64::MAX));
+        assert_eq!(gallop(i64::MIN, |v| v >= -(1 << 20)), Some(-(1 << 20)));
+        assert_eq!(gallop(1u64, |v| v > 1 << 41), Some((1 << 41) + 1));
+    }
+
+    #[test]
+    fn ternary_matches_linear_scans() {
+        for peak in -20..20i32 {
+            for width in 0..12 {
+                let f = |v: i32| (width - (v - peak).abs()).min(0);
+                let max = (-15..15).map(|v| (f(v), -v)).max().map(|(fv, v)| (-v, fv));
+                assert_eq!(ternary_max(-15, 15, f), max);
+
+                let g = |v: i32| (v - peak) * (v - peak) + width;
+                let min = (-15..15).map(|v| (g(v), v)).min().map(|(gv, v)| (v, gv));
+                assert_eq!(ternary_min(-15, 15, g), min);
+            }
+        }
+
+        assert_eq!(ternary_max(5, 5, |v: i32| v), None);
+        assert_eq!(ternary_max(i64::MIN, i64::MAX, |v| -(v.abs_diff(1000) as i128)), Some((1000, 0)));
+    }
+}
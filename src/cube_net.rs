@@ -0,0 +1,273 @@
+use crate::geo::Point;
+use crate::grid2::{FixedGrid, GetterGrid, TileGrid};
+
+/// The directions and edges are numbered like the facing in most grid puzzles: clockwise from
+/// the right. Edge `RIGHT` is the edge a cursor crosses when it moves right.
+pub const RIGHT: u8 = 0;
+pub const DOWN: u8 = 1;
+pub const LEFT: u8 = 2;
+pub const UP: u8 = 3;
+
+type Vec3 = [i8; 3];
+
+/// Wrap is where a cursor ends up after moving off a face's edge.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Wrap {
+    pub face: usize,
+    /// The edge of `face` that is crossed.
+    pub edge: u8,
+    /// The direction the cursor faces on the new face, which is always away from `edge`.
+    pub dir: u8,
+}
+
+/// CubeNet is a cube net in a grid, folded into a cube. The faces are numbered in reading order
+/// of their tiles, and every edge of every face is connected to the edge it meets on the cube.
+#[derive(Clone, Debug)]
+pub struct CubeNet {
+    side: usize,
+    tiles: [Point<usize>; 6],
+    wraps: [[Wrap; 4]; 6],
+}
+
+impl CubeNet {
+    /// Find the six `side`×`side` faces in the grid, where every other tile is entirely `void`,
+    /// and fold them. It returns `None` if the tiles aren't a cube net.
+    pub fn fold<G, T>(grid: &G, side: usize, void: &T) -> Option<CubeNet> where G: GetterGrid<T> + FixedGrid, T: Eq {
+        let mut tiles = [Point(0, 0); 6];
+        let mut count = 0;
+        for (index, tile) in grid.tiles(side, side).void(void) {
+            if count == 6 || tile.width() != side || tile.height() != side {
+                return None;
+            }
+
+            tiles[count] = index;
+            count += 1;
+        }
+        if count != 6 {
+            return None;
+        }
+
+        // Fold the faces away from the viewer, starting with the first one facing them. A
+        // frame is the outward normal and the 3D direction of the face's right and down.
+        let mut frames: [Option<[Vec3; 3]>; 6] = [None; 6];
+        frames[0] = Some([[0, 0, -1], [1, 0, 0], [0, 1, 0]]);
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let [n, r, d] = frames[i].unwrap();
+            let Point(x, y) = tiles[i];
+
+            let adjacent = [
+                (Point(x + 1, y), [r, neg(n), d]),
+                (Point(x, y + 1), [d, r, neg(n)]),
+                (Point(x.wrapping_sub(1), y), [neg(r), n, d]),
+                (Point(x, y.wrapping_sub(1)), [neg(d), r, n]),
+            ];
+            for (tile, frame) in adjacent {
+                if let Some(j) = tiles.iter().position(|t| *t == tile) {
+                    if frames[j].is_none() {
+                        frames[j] = Some(frame);
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+
+        let frames: Vec<[Vec3; 3]> = frames.iter().copied().collect::<Option<_>>()?;
+        for i in 0..6 {
+            if frames[i + 1..].iter().any(|f| f[0] == frames[i][0]) {
+                return None;
+            }
+        }
+
+        let mut wraps = [[Wrap { face: 0, edge: 0, dir: 0 }; 4]; 6];
+        for (i, [n, r, d]) in frames.iter().enumerate() {
+            for (edge, exit) in [*r, *d, neg(*r), neg(*d)].into_iter().enumerate() {
+                // Moving off the edge goes around the cube, and continues into it on the
+                // other face.
+                let face = frames.iter().position(|f| f[0] == exit).unwrap();
+                let [_, fr, fd] = frames[face];
+                let dir = [fr, fd, neg(fr), neg(fd)].iter().position(|v| *v == neg(*n)).unwrap() as u8;
+
+                wraps[i][edge] = Wrap { face, edge: (dir + 2) % 4, dir };
+            }
+        }
+
+        Some(CubeNet { side, tiles, wraps })
+    }
+
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// Get the tile index of the face, where `Point(1, 0)` is the second tile on the first row.
+    pub fn tile(&self, face: usize) -> Point<usize> {
+        self.tiles[face]
+    }
+
+    /// Get the position in the grid of the face's top left corner.
+    pub fn offset(&self, face: usize) -> Point<usize> {
+        Point(self.tiles[face].0 * self.side, self.tiles[face].1 * self.side)
+    }
+
+    /// Get the face and the position on it of a position in the grid.
+    pub fn face_at(&self, pos: &Point<usize>) -> Option<(usize, Point<usize>)> {
+        let tile = Point(pos.0 / self.side, pos.1 / self.side);
+        let face = self.tiles.iter().position(|t| *t == tile)?;
+
+        Some((face, Point(pos.0 % self.side, pos.1 % self.side)))
+    }
+
+    pub fn wrap(&self, face: usize, edge: u8) -> Wrap {
+        self.wraps[face][edge as usize]
+    }
+
+    /// Move one step from a position on a face, wrapping around the cube if it's on the edge.
+    pub fn step(&self, face: usize, pos: &Point<usize>, dir: u8) -> (usize, Point<usize>, u8) {
+        let far = self.side - 1;
+        let Point(x, y) = *pos;
+
+        match dir {
+            RIGHT if x < far => return (face, Point(x + 1, y), dir),
+            DOWN if y < far => return (face, Point(x, y + 1), dir),
+            LEFT if x > 0 => return (face, Point(x - 1, y), dir),
+            UP if y > 0 => return (face, Point(x, y - 1), dir),
+            _ => {}
+        }
+
+        // Going clockwise around a face is counter-clockwise for the same edge on the other
+        // face, since they're both seen from the outside.
+        let along = match dir {
+            RIGHT => y,
+            DOWN => far - x,
+            LEFT => far - y,
+            UP => x,
+            _ => panic!("CubeNet::step: unknown direction {}", dir),
+        };
+        let wrap = self.wraps[face][dir as usize];
+        let along = far - along;
+        let pos = match wrap.edge {
+            RIGHT => Point(far, along),
+            DOWN => Point(far - along, far),
+            LEFT => Point(0, far - along),
+            _ => Point(along, 0),
+        };
+
+        (wrap.face, pos, wrap.dir)
+    }
+}
+
+#[inline]
+fn neg(v: Vec3) -> Vec3 {
+    [-v[0], -v[1], -v[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid2::{GetterMutGrid, IterableSliceGrid, VecGrid};
+    use super::*;
+
+    const NETS: &[&[u8]] = &[
+        b"#...\n####\n#...\n",
+        b"#...\n####\n.#..\n",
+        b"#...\n####\n..#.\n",
+        b"#...\n####\n...#\n",
+        b".#..\n####\n.#..\n",
+        b".#..\n####\n..#.\n",
+        b"##..\n.###\n.#..\n",
+        b"##..\n.###\n..#.\n",
+        b"##..\n.###\n...#\n",
+        b"##..\n.##.\n..##\n",
+        b"###..\n..###\n",
+    ];
+
+    fn orientations(net: &[u8]) -> Vec<VecGrid<u8>> {
+        let grid = VecGrid::parse_lines(net, b'\n').unwrap();
+        let mut cells: Vec<Point<usize>> = grid.cells()
+            .filter(|(_, v)| **v == b'#')
+            .map(|(p, _)| p)
+            .collect();
+
+        let mut res = Vec::new();
+        for flip in [false, true] {
+            for _ in 0..4 {
+                // Rotate a quarter turn, and flip it horizontally for the mirrored orientations.
+                let height = cells.iter().map(|p| p.1).max().unwrap() + 1;
+                cells = cells.iter().map(|p| Point(height - 1 - p.1, p.0)).collect();
+
+                let width = cells.iter().map(|p| p.0).max().unwrap() + 1;
+                let height = cells.iter().map(|p| p.1).max().unwrap() + 1;
+                let mut grid = VecGrid::new_with(width * 3, height * 3, b' ');
+                for p in cells.iter() {
+                    let x = if flip { width - 1 - p.0 } else { p.0 };
+                    for dy in 0..3 {
+                        for dx in 0..3 {
+                            *grid.get_mut(&Point(x * 3 + dx, p.1 * 3 + dy)).unwrap() = b'.';
+                        }
+                    }
+                }
+
+                res.push(grid);
+            }
+        }
+
+        res
+    }
+
+    #[test]
+    fn folds_all_nets() {
+        for net in NETS {
+            for grid in orientations(net) {
+                let cube = CubeNet::fold(&grid, 3, &b' ').expect("not folded");
+
+                for face in 0..6 {
+                    let mut neighbors: Vec<usize> = (0..4).map(|e| cube.wrap(face, e).face).collect();
+                    neighbors.sort();
+                    neighbors.dedup();
+                    assert_eq!(neighbors.len(), 4);
+                    assert!(!neighbors.contains(&face));
+
+                    for edge in 0..4 {
+                        let wrap = cube.wrap(face, edge);
+                        assert_eq!(cube.wrap(wrap.face, wrap.edge), Wrap { face, edge, dir: (edge + 2) % 4 });
+                    }
+
+                    // Walking straight ahead goes around the cube and back.
+                    for dir in 0..4 {
+                        for (start, _) in grid.cells().filter(|(p, _)| cube.face_at(p).map(|(f, _)| f) == Some(face)) {
+                            let start = Point(start.0 % 3, start.1 % 3);
+                            let mut current = (face, start, dir);
+                            for _ in 0..12 {
+                                current = cube.step(current.0, &current.1, current.2);
+                                let offset = cube.offset(current.0);
+                                assert_eq!(cube.face_at(&(offset + current.1)), Some((current.0, current.1)));
+                            }
+
+                            assert_eq!(current, (face, start, dir));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn steps_match_the_net() {
+        let grid = VecGrid::parse_lines(b"  ..  \n  ..  \n......\n......\n  ..  \n  ..  \n  ..  \n  ..  \n".as_slice(), b'\n').unwrap();
+        let cube = CubeNet::fold(&grid, 2, &b' ').unwrap();
+
+        assert_eq!(cube.tile(0), Point(1, 0));
+        assert_eq!(cube.face_at(&Point(4, 3)), Some((3, Point(0, 1))));
+        assert_eq!(cube.step(1, &Point(1, 0), RIGHT), (2, Point(0, 0), RIGHT));
+        assert_eq!(cube.step(0, &Point(0, 0), UP), (5, Point(0, 1), UP));
+        assert_eq!(cube.step(0, &Point(0, 1), LEFT), (1, Point(1, 0), DOWN));
+        assert_eq!(cube.step(3, &Point(1, 1), RIGHT), (5, Point(1, 0), LEFT));
+    }
+
+    #[test]
+    fn rejects_other_shapes() {
+        for shape in [b"###\n###\n".as_slice(), b"######\n", b"####\n.##.\n", b"#####\n#....\n", b"####\n####\n"] {
+            let grid = VecGrid::parse_lines(shape, b'\n').unwrap();
+            assert!(CubeNet::fold(&grid, 1, &b'.').is_none(), "{}", String::from_utf8_lossy(shape));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::hash::{Hash, Hasher};
+use rustc_hash::{FxHashMap, FxHasher};
+use crate::geo::{Point, Rect};
+use crate::grid2::{FixedGrid, GetterGrid, IterableSliceGrid};
+
+/// Hash any state with the same hasher that is used for the grids.
+pub fn hash_state<S: Hash + ?Sized>(state: &S) -> u64 {
+    let mut hasher = FxHasher::default();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash the size and every cell of the grid.
+pub fn hash_grid<G, T>(grid: &G) -> u64 where G: IterableSliceGrid<T> + FixedGrid, T: Hash {
+    let mut hasher = FxHasher::default();
+    grid.width().hash(&mut hasher);
+    grid.height().hash(&mut hasher);
+    for (_, v) in grid.cells() {
+        v.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+/// Hash the cells inside the rect. Cells outside the grid are hashed as missing, so a
+/// window partially outside the grid is still hashed consistently.
+pub fn hash_window<G, T>(grid: &G, rect: &Rect<usize>) -> u64 where G: GetterGrid<T>, T: Hash {
+    let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+
+    let mut hasher = FxHasher::default();
+    for y in y1..y2 {
+        for x in x1..x2 {
+            grid.get(&Point(x, y)).hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Cycle {
+    /// The first step that is part of the cycle.
+    pub start: usize,
+    /// The number of steps until the state repeats.
+    pub length: usize,
+}
+
+/// CycleDetector finds the first repeated state in a simulation. The state key should include
+/// everything the next steps depend on (e.g. a grid hash and the instruction index). A metric
+/// like height or score is recorded per step so that it can be extrapolated.
+pub struct CycleDetector<K> {
+    seen: FxHashMap<K, usize>,
+    metrics: Vec<i64>,
+    cycle: Option<Cycle>,
+}
+
+impl<K> CycleDetector<K> where K: Hash + Eq {
+    pub fn new() -> Self {
+        Self {
+            seen: FxHashMap::default(),
+            metrics: Vec::with_capacity(1024),
+            cycle: None,
+        }
+    }
+
+    /// Record the key and metric for the next step, starting with step 0. It returns the cycle
+    /// once a key has been seen before.
+    pub fn push(&mut self, key: K, metric: i64) -> Option<Cycle> {
+        if self.cycle.is_some() {
+            return self.cycle;
+        }
+
+        let step = self.metrics.len();
+        self.metrics.push(metric);
+
+        if let Some(start) = self.seen.insert(key, step) {
+            self.cycle = Some(Cycle { start, length: step - start });
+        }
+
+        self.cycle
+    }
+
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    pub fn steps(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Get the metric at any step. Steps before the cycle has been found must have been pushed,
+    /// and steps past it are extrapolated by adding the metric's change per cycle.
+    pub fn metric_at(&self, step: usize) -> Option<i64> {
+        if step < self.metrics.len() {
+            return Some(self.metrics[step]);
+        }
+
+        let Cycle { start, length } = self.cycle?;
+        let per_cycle = self.metrics[start + length] - self.metrics[start];
+        let cycles = (step - start) / length;
+        let offset = (step - start) % length;
+
+        Some(self.metrics[start + offset] + per_cycle * cycles as i64)
+    }
+}
+
+impl<K> Default for CycleDetector<K> where K: Hash + Eq {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid2::VecGrid;
+    use super::*;
+
+    #[test]
+    fn detects_and_extrapolates() {
+        // 0 1 2 3 4 [5 6 7] [5 6 7] ...
+        let states = (0..20u32).map(|i| if i < 5 { i } else { 5 + (i - 5) % 3 });
+        let mut detector = CycleDetector::new();
+        let mut found = None;
+        for (step, state) in states.enumerate() {
+            found = detector.push(state, step as i64 * 2);
+            if found.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(found, Some(Cycle { start: 5, length: 3 }));
+        assert_eq!(detector.steps(), 9);
+        assert_eq!(detector.metric_at(3), Some(6));
+        assert_eq!(detector.metric_at(1000), Some(2000));
+        assert_eq!(CycleDetector::<u32>::new().metric_at(1000), None);
+    }
+
+    #[test]
+    fn grid_hashes() {
+        let a = VecGrid::parse_lines(b"#..\n.#.\n", b'\n').unwrap();
+        let b = VecGrid::parse_lines(b"#...#.\n", b'\n').unwrap();
+        let c = VecGrid::parse_lines(b"##..\n..#.\n", b'\n').unwrap();
+
+        assert_eq!(hash_grid(&a), hash_grid(&a.clone()));
+        assert_ne!(hash_grid(&a), hash_grid(&b));
+        assert_eq!(hash_window(&a, &Rect(Point(1, 0), Point(3, 2))), hash_window(&c, &Rect(Point(2, 0), Point(4, 2))));
+        assert_eq!(hash_window(&a, &Rect(Point(1, 0), Point(4, 2))), hash_window(&c, &Rect(Point(2, 0), Point(5, 2))));
+        assert_ne!(hash_window(&a, &Rect(Point(0, 0), Point(2, 2))), hash_window(&c, &Rect(Point(2, 0), Point(4, 2))));
+    }
+}
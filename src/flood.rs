@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use num::traits::WrappingAdd;
+use crate::geo::Point;
+use crate::grid2::{CARDINALS, FixedGrid, GetterGrid, GetterMutGrid, NEIGHBORS, VecGrid};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Neighborhood {
+    /// Up, left, right and down.
+    Cardinals,
+    /// The cardinals and the diagonals.
+    All,
+}
+
+/// FloodSpace is anything with cells that can be flooded: a 2D grid with `Point` positions or a
+/// voxel grid with `Vertex` positions. `Map` is a grid of the same size to store results in.
+pub trait FloodSpace<T> {
+    type Pos: Copy;
+    type Map<V: Copy>: FloodMap<V, Pos=Self::Pos>;
+
+    fn at(&self, pos: &Self::Pos) -> Option<&T>;
+    fn for_each_neighbor<F: FnMut(Self::Pos)>(&self, pos: &Self::Pos, neighborhood: Neighborhood, f: F);
+    /// Visit all positions in reading order.
+    fn for_each_pos<F: FnMut(Self::Pos)>(&self, f: F);
+    fn new_map<V: Copy>(&self, v: V) -> Self::Map<V>;
+}
+
+pub trait FloodMap<V> {
+    type Pos;
+
+    fn at_mut(&mut self, pos: &Self::Pos) -> Option<&mut V>;
+}
+
+impl<G, T> FloodSpace<T> for G where G: GetterGrid<T> + FixedGrid {
+    type Pos = Point<usize>;
+    type Map<V: Copy> = VecGrid<V>;
+
+    #[inline]
+    fn at(&self, pos: &Point<usize>) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn for_each_neighbor<F: FnMut(Point<usize>)>(&self, pos: &Point<usize>, neighborhood: Neighborhood, mut f: F) {
+        let offsets = match neighborhood {
+            Neighborhood::Cardinals => CARDINALS.as_slice(),
+            Neighborhood::All => NEIGHBORS.as_slice(),
+        };
+
+        for off in offsets {
+            let next = pos.wrapping_add(off);
+            if next.0 < self.width() && next.1 < self.height() {
+                f(next);
+            }
+        }
+    }
+
+    fn for_each_pos<F: FnMut(Point<usize>)>(&self, mut f: F) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                f(Point(x, y));
+            }
+        }
+    }
+
+    fn new_map<V: Copy>(&self, v: V) -> VecGrid<V> {
+        VecGrid::new_with(self.width(), self.height(), v)
+    }
+}
+
+impl<V> FloodMap<V> for VecGrid<V> {
+    type Pos = Point<usize>;
+
+    #[inline]
+    fn at_mut(&mut self, pos: &Point<usize>) -> Option<&mut V> {
+        self.get_mut(pos)
+    }
+}
+
+/// Flood is a breadth-first flood over the cells of a grid. Cells can be blocked with `passable`,
+/// and moves between cells with `edge`, which gets the value of the cell moved from first.
+pub struct Flood<'g, G, T, P, E> {
+    grid: &'g G,
+    neighborhood: Neighborhood,
+    passable: P,
+    edge: E,
+    reversed: bool,
+    spooky_ghost: PhantomData<T>,
+}
+
+impl<'g, G, T> Flood<'g, G, T, fn(&T) -> bool, fn(&T, &T) -> bool> where G: FloodSpace<T> {
+    pub fn new(grid: &'g G) -> Self {
+        Flood {
+            grid,
+            neighborhood: Neighborhood::Cardinals,
+            passable: |_| true,
+            edge: |_, _| true,
+            reversed: false,
+            spooky_ghost: PhantomData,
+        }
+    }
+}
+
+impl<'g, G, T, P, E> Flood<'g, G, T, P, E> where G: FloodSpace<T>, P: Fn(&T) -> bool, E: Fn(&T, &T) -> bool {
+    pub fn neighborhood(self, neighborhood: Neighborhood) -> Self {
+        Self { neighborhood, ..self }
+    }
+
+    pub fn passable<P2: Fn(&T) -> bool>(self, passable: P2) -> Flood<'g, G, T, P2, E> {
+        Flood {
+            grid: self.grid,
+            neighborhood: self.neighborhood,
+            passable,
+            edge: self.edge,
+            reversed: self.reversed,
+            spooky_ghost: PhantomData,
+        }
+    }
+
+    pub fn edge<E2: Fn(&T, &T) -> bool>(self, edge: E2) -> Flood<'g, G, T, P, E2> {
+        Flood {
+            grid: self.grid,
+            neighborhood: self.neighborhood,
+            passable: self.passable,
+            edge,
+            reversed: self.reversed,
+            spooky_ghost: PhantomData,
+        }
+    }
+
+    /// Follow the edges backwards, so that a flood from the goal finds every cell that can
+    /// reach it under the same rule.
+    pub fn reversed(self) -> Self {
+        Self { reversed: !self.reversed, ..self }
+    }
+
+    /// Get all cells reachable from `start`.
+    pub fn fill(&self, start: G::Pos) -> G::Map<bool> {
+        let mut filled = self.grid.new_map(false);
+        self.spread([start], |p, _| mark(&mut filled, p, true, |v| !*v));
+
+        filled
+    }
+
+    /// Get the number of steps to every cell reachable from `start`.
+    pub fn distances(&self, start: G::Pos) -> G::Map<Option<u32>> {
+        self.distances_from([start])
+    }
+
+    /// Get the number of steps from the closest start to every reachable cell.
+    pub fn distances_from<I: IntoIterator<Item=G::Pos>>(&self, starts: I) -> G::Map<Option<u32>> {
+        let mut distances = self.grid.new_map(None);
+        self.spread(starts, |p, d| mark(&mut distances, p, Some(d), Option::is_none));
+
+        distances
+    }
+
+    /// Label the connected regions of passable cells in reading order. The returned list has
+    /// the size of each region by label.
+    pub fn components(&self) -> (G::Map<Option<u32>>, Vec<usize>) {
+        let mut labels = self.grid.new_map(None);
+        let mut sizes = Vec::new();
+
+        self.grid.for_each_pos(|p| {
+            if labels.at_mut(&p).unwrap().is_some() {
+                return;
+            }
+
+            let label = sizes.len() as u32;
+            let size = self.spread([p], |p, _| mark(&mut labels, p, Some(label), Option::is_none));
+            if size > 0 {
+                sizes.push(size);
+            }
+        });
+
+        (labels, sizes)
+    }
+
+    fn spread<I, M>(&self, starts: I, mut mark: M) -> usize
+        where I: IntoIterator<Item=G::Pos>,
+              M: FnMut(&G::Pos, u32) -> bool {
+        let mut queue = VecDeque::with_capacity(64);
+        let mut count = 0;
+
+        for p in starts {
+            if let Some(v) = self.grid.at(&p) {
+                if (self.passable)(v) && mark(&p, 0) {
+                    queue.push_back((p, 0));
+                    count += 1;
+                }
+            }
+        }
+
+        while let Some((p, dist)) = queue.pop_front() {
+            let current = self.grid.at(&p).unwrap();
+
+            self.grid.for_each_neighbor(&p, self.neighborhood, |next_p| {
+                let next = self.grid.at(&next_p).unwrap();
+                if !(self.passable)(next) {
+                    return;
+                }
+                let can_move = if self.reversed {
+                    (self.edge)(next, current)
+                } else {
+                    (self.edge)(current, next)
+                };
+
+                if can_move && mark(&next_p, dist + 1) {
+                    queue.push_back((next_p, dist + 1));
+                    count += 1;
+                }
+            });
+        }
+
+        count
+    }
+}
+
+#[inline]
+fn mark<M, V, F>(map: &mut M, p: &M::Pos, v: V, unvisited: F) -> bool where M: FloodMap<V>, F: Fn(&V) -> bool {
+    let cell = map.at_mut(p).unwrap();
+    if unvisited(cell) {
+        *cell = v;
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid2::IterableSliceGrid;
+    use crate::parse3;
+    use crate::parse3::Parser;
+    use super::*;
+
+    const D12_EXAMPLE: &[u8] = b"Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n";
+
+    #[test]
+    fn distances_climbing() {
+        let (grid, markers) = parse3::grid(|b| match b {
+            b'S' => 0u8,
+            b'E' => 25,
+            b => b - b'a',
+        }).with_markers(b"SE").parse(D12_EXAMPLE).unwrap();
+        let start = markers[0].1;
+        let end = markers[1].1;
+
+        let flood = Flood::new(&grid).edge(|from, to| *to <= *from + 1);
+        assert_eq!(*flood.distances(start).get(&end).unwrap(), Some(31));
+
+        let from_end = flood.reversed().distances(end);
+        let closest_a = grid.cells()
+            .filter(|(_, v)| **v == 0)
+            .filter_map(|(p, _)| *from_end.get(&p).unwrap())
+            .min();
+        assert_eq!(closest_a, Some(29));
+    }
+
+    #[test]
+    fn fill_and_components() {
+        let grid = parse3::grid(|b| b == b'.').parse(b"..#..\n..#.#\n#.#..\n.#...\n").unwrap();
+
+        let flood = Flood::new(&grid).passable(|v| *v);
+        let filled = flood.fill(Point(0, 0));
+        assert_eq!(filled.cells().filter(|(_, v)| **v).count(), 5);
+        assert_eq!(filled.get(&Point(3, 0)), Some(&false));
+
+        let (labels, sizes) = flood.components();
+        assert_eq!(sizes, vec![5, 8, 1]);
+        assert_eq!(labels.get(&Point(4, 3)), Some(&Some(1)));
+        assert_eq!(labels.get(&Point(2, 0)), Some(&None));
+
+        let distances = flood.distances_from([Point(0, 0), Point(4, 3)]);
+        assert_eq!(distances.get(&Point(1, 1)), Some(&Some(2)));
+        assert_eq!(distances.get(&Point(3, 0)), Some(&Some(4)));
+        assert_eq!(distances.get(&Point(0, 3)), Some(&None));
+
+        let (_, sizes) = flood.neighborhood(Neighborhood::All).components();
+        assert_eq!(sizes, vec![14]);
+    }
+}
@@ -1,3 +1,7 @@
+use std::hash::Hash;
+use std::ops::Deref;
+use rustc_hash::FxHashMap;
+
 /// A basic node graph meant for small graphs for path traversal. It does not contain the data in
 /// a map, and is most useful for traversal rather than lookup.
 ///
@@ -115,6 +119,107 @@ impl<N, E> Graph<N, E> where E: Copy {
     }
 }
 
+/// A graph that also keeps a map from a key of each node to its index, so that nodes can be found
+/// by their key without scanning. The index based methods of `Graph` can be used through deref,
+/// and only one node can have each key.
+///
+/// ```rust
+/// use common::graph::KeyedGraph;
+///
+/// let mut g: KeyedGraph<(&str, u32), u32, &str> = KeyedGraph::new(|(name, _)| *name);
+/// let london = g.insert(("London", 9));
+/// g.find_or_insert(("Dublin", 1));
+/// g.find_or_insert(("London", 2));
+///
+/// assert!(g.connect_by_key(&"London", &"Dublin", 464));
+/// assert!(!g.connect_by_key(&"London", &"Belfast", 518));
+/// assert_eq!(g.len(), 2);
+/// assert_eq!(g.find(&"London"), Some(london));
+/// assert_eq!(g.get_by_key(&"London"), Some(&("London", 9)));
+/// assert_eq!(g.edge(london, g.find(&"Dublin").unwrap()), Some(&464));
+/// ```
+pub struct KeyedGraph<N, E, K> {
+    graph: Graph<N, E>,
+    key: fn(&N) -> K,
+    index: FxHashMap<K, usize>,
+}
+
+impl<N, E, K> KeyedGraph<N, E, K> where K: Hash + Eq {
+    pub fn new(key: fn(&N) -> K) -> Self {
+        Self {
+            graph: Graph::new(),
+            key,
+            index: FxHashMap::default(),
+        }
+    }
+
+    pub fn find(&self, key: &K) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    /// Insert the node, or replace the node with the same key and keep its edges.
+    pub fn insert(&mut self, value: N) -> usize {
+        let key = (self.key)(&value);
+        if let Some(index) = self.find(&key) {
+            *self.graph.node_mut(index).unwrap() = value;
+            index
+        } else {
+            let index = self.graph.insert(value);
+            self.index.insert(key, index);
+            index
+        }
+    }
+
+    pub fn find_or_insert(&mut self, value: N) -> usize {
+        match self.find(&(self.key)(&value)) {
+            Some(index) => index,
+            None => self.insert(value),
+        }
+    }
+
+    pub fn get_by_key(&self, key: &K) -> Option<&N> {
+        self.find(key).and_then(|index| self.graph.node(index))
+    }
+
+    /// Get the node to change it. Its key must stay the same.
+    pub fn node_mut(&mut self, index: usize) -> Option<&mut N> {
+        self.graph.node_mut(index)
+    }
+
+    pub fn connect(&mut self, a: usize, b: usize, e: E) {
+        self.graph.connect(a, b, e)
+    }
+
+    /// Connect the nodes with the keys, and return whether both of them exist.
+    pub fn connect_by_key(&mut self, a: &K, b: &K, e: E) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => {
+                self.graph.connect(a, b, e);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn into_graph(self) -> Graph<N, E> {
+        self.graph
+    }
+}
+
+impl<N, E, K> KeyedGraph<N, E, K> where K: Hash + Eq, E: Copy {
+    pub fn connect_mutual(&mut self, a: usize, b: usize, e: E) {
+        self.graph.connect_mutual(a, b, e)
+    }
+}
+
+impl<N, E, K> Deref for KeyedGraph<N, E, K> {
+    type Target = Graph<N, E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
 struct Node<N, E> {
     data: N,
     edges: Vec<(usize, E)>,
@@ -1,5 +1,8 @@
 use crate::geo::Point;
+use crate::grid2::{ClearableGrid, ColumnGrid, CountableGrid, FillableGrid, FixedGrid, GetterGrid, GetterMutGrid, IterableSliceGrid, RectGrid, RowGrid, SliceIter};
+use std::iter::StepBy;
 use std::ops::{Index, IndexMut, Range};
+use std::slice::{Iter, IterMut};
 use smallvec::{SmallVec, smallvec, Array};
 use std::marker::PhantomData;
 
@@ -47,7 +50,7 @@ impl<T, S> Copy for Grid<T, S> where S: Copy, T: Copy {}
 impl<T, S> Grid<T, S> where S: Index<usize, Output=T> {
     pub fn cell(&self, p: Point<usize>) -> Option<&T> {
         if p.0 < self.width && p.1 < self.height {
-            Some(&self.data[p.0 * self.width + p.1])
+            Some(&self.data[p.1 * self.width + p.0])
         } else {
             None
         }
@@ -64,29 +67,6 @@ impl<'a, T: 'a, S> Grid<T, S> where S: AsRef<[T]> {
             (Point(i % self.width, i / self.width), v)
         })
     }
-
-    pub fn print(&'a self, cb: fn(v: &T) -> (char, Option<String>)) {
-        let mut buffer: Vec<String> = Vec::new();
-
-        for (Point(_, y), v) in self.iter() {
-            if y == 0 {
-                for s in buffer.iter() {
-                    print!(" {}", s)
-                }
-                buffer.clear();
-                println!();
-            }
-
-            let (c, s) = cb(v);
-            if let Some(s) = s {
-                buffer.push(s);
-            }
-
-            print!("{}", c);
-        }
-
-        println!();
-    }
 }
 
 impl<T, const SIZE: usize> Grid<T, [T; SIZE]> {
@@ -169,26 +149,6 @@ impl<T, S> Grid<T, S> where S: IndexMut<Range<usize>, Output=[T]> {
             false
         }
     }
-
-    pub fn map_rect<F>(&mut self, p1: Point<usize>, p2: Point<usize>, f: F) -> bool where F: Fn(&mut T, Point<usize>) {
-        let Point(x1, y1) = p1;
-        let Point(x2, y2) = p2;
-        if x2 <= x1 || y2 <= y1 {
-            return false;
-        }
-
-        let w = x2 - x1;
-        for y in y1..y2 {
-            let s = self.width * y + x1;
-            let e = s + w;
-
-            for (i, v) in self.data[s..e].iter_mut().enumerate() {
-                f(v, Point(x1 + i, y));
-            }
-        }
-
-        false
-    }
 }
 
 impl<T, S> Grid<T, S> where S: IndexMut<Range<usize>, Output=[T]>, T: Copy {
@@ -215,26 +175,6 @@ impl<T, S> Grid<T, S> where S: IndexMut<Range<usize>, Output=[T]>, T: Copy {
             false
         }
     }
-
-    pub fn fill_rect(&mut self, p1: Point<usize>, p2: Point<usize>, v: T) -> bool {
-        let Point(x1, y1) = p1;
-        let Point(x2, y2) = p2;
-        if x2 <= x1 || y2 <= y1 {
-            return false;
-        }
-
-        let w = x2 - x1;
-        for y in y1..y2 {
-            let s = self.width * y + x1;
-            let e = s + w;
-
-            for v2 in self.data[s..e].iter_mut() {
-                *v2 = v;
-            }
-        }
-
-        false
-    }
 }
 
 impl<T, S> Grid<T, S> where S: Index<usize, Output=T> {
@@ -326,5 +266,128 @@ impl<T, A: Array<Item=T>> Grid<T, SmallVec<A>> where T: Default {
     }
 }
 
+impl<T, S> FixedGrid for Grid<T, S> {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl<T, S> GetterGrid<T> for Grid<T, S> where S: AsRef<[T]> {
+    fn get(&self, pos: &Point<usize>) -> Option<&T> {
+        if pos.0 >= self.width || pos.1 >= self.height {
+            return None;
+        }
+
+        self.data.as_ref().get(pos.1 * self.width + pos.0)
+    }
+}
+
+impl<T, S> GetterMutGrid<T> for Grid<T, S> where S: AsRef<[T]> + AsMut<[T]> {
+    fn get_mut(&mut self, pos: &Point<usize>) -> Option<&mut T> {
+        if pos.0 >= self.width || pos.1 >= self.height {
+            return None;
+        }
+
+        self.data.as_mut().get_mut(pos.1 * self.width + pos.0)
+    }
+}
+
+impl<T, S> RowGrid<T> for Grid<T, S> where S: AsRef<[T]> + AsMut<[T]> {
+    fn row(&self, y: usize) -> Option<&[T]> {
+        if y >= self.height {
+            return None;
+        }
+
+        Some(&self.data.as_ref()[y * self.width..(y + 1) * self.width])
+    }
+
+    fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        if y >= self.height {
+            return None;
+        }
+
+        Some(&mut self.data.as_mut()[y * self.width..(y + 1) * self.width])
+    }
+}
+
+impl<T, S> ColumnGrid<T> for Grid<T, S> where S: AsRef<[T]> + AsMut<[T]> {
+    fn column(&self, x: usize) -> Option<StepBy<Iter<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+
+        Some(self.data.as_ref()[x..].iter().step_by(self.width))
+    }
+
+    fn column_mut(&mut self, x: usize) -> Option<StepBy<IterMut<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+
+        Some(self.data.as_mut()[x..].iter_mut().step_by(self.width))
+    }
+}
+
+impl<T, S> RectGrid<T> for Grid<T, S> where S: AsRef<[T]> + AsMut<[T]> {}
+
+impl<T, S> IterableSliceGrid<T> for Grid<T, S> where S: AsRef<[T]> {
+    fn cells(&self) -> SliceIter<'_, T> {
+        SliceIter::new(self.data.as_ref(), self.width)
+    }
+}
+
+impl<T, S> CountableGrid<T> for Grid<T, S> where S: AsRef<[T]>, T: Eq {
+    fn count_occurrences_of(&self, pred: &T) -> usize {
+        self.data.as_ref().iter().filter(|v| pred.eq(*v)).count()
+    }
+
+    fn count_occurrences_where<F>(&self, pred: F) -> usize where F: Fn(&T) -> bool {
+        self.data.as_ref().iter().filter(|v| pred(*v)).count()
+    }
+}
+
+impl<T, S> FillableGrid<T> for Grid<T, S> where S: AsMut<[T]>, T: Copy {
+    fn fill(&mut self, v: T) {
+        self.data.as_mut().fill(v);
+    }
+}
+
+impl<T, S> ClearableGrid for Grid<T, S> where S: AsMut<[T]>, T: Copy + Default {
+    fn clear(&mut self) {
+        self.data.as_mut().fill(T::default());
+    }
+}
+
 pub type ArrayGrid<T, const S: usize> = Grid<T, [T; S]>;
 pub type VecGrid<T> = Grid<T, Vec<T>>;
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::Rect;
+    use crate::grid2::render_grid;
+    use super::*;
+
+    #[test]
+    fn grid2_traits() {
+        let mut grid: VecGrid<u8> = Grid::new_vec(4, 3, 0);
+        assert_eq!(grid.fill_rect(&Rect(Point(1, 0), Point(3, 2)), 1), true);
+        *grid.get_mut(&Point(3, 2)).unwrap() = 2;
+
+        assert_eq!(grid.get(&Point(3, 2)), Some(&2));
+        assert_eq!(grid.get(&Point(4, 2)), None);
+        assert_eq!(grid.cell(Point(3, 2)), Some(&2));
+        assert_eq!(grid.count_occurrences_of(&1), 4);
+        assert_eq!(grid.column(1).unwrap().copied().collect::<Vec<_>>(), vec![1, 1, 0]);
+        assert_eq!(
+            render_grid(&grid, |v| match v {
+                0 => ('.', None),
+                1 => ('#', None),
+                _ => ('@', Some("<- here".to_string())),
+            }),
+            ".##.\n.##.\n...@ <- here\n",
+        );
+    }
+}
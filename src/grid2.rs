@@ -1,7 +1,10 @@
 use std::fmt::Display;
-use std::ops::Add;
-use num::traits::WrappingAdd;
-use crate::geo::Point;
+use std::iter::StepBy;
+use std::marker::PhantomData;
+use std::ops::{Add, Not, Range, Sub};
+use std::slice::{Iter, IterMut};
+use num::traits::{WrappingAdd, Zero};
+use crate::geo::{Point, Rect};
 
 pub const NEIGHBORS: &'static [Point<usize>; 8] = &[
     Point(usize::MAX, usize::MAX),
@@ -14,6 +17,14 @@ pub const NEIGHBORS: &'static [Point<usize>; 8] = &[
     Point(1, 1),
 ];
 
+/// The cardinal directions in the same order as `Point::cardinals`.
+pub const CARDINALS: &[Point<usize>; 4] = &[
+    Point(0, usize::MAX),
+    Point(usize::MAX, 0),
+    Point(1, 0),
+    Point(0, 1),
+];
+
 pub struct ArrayGrid<T, const S: usize, const W: usize> {
     data: [T; S],
 }
@@ -136,6 +147,26 @@ impl<T, const S: usize, const W: usize> GetterMutGrid<T> for ArrayGrid<T, S, W>
     }
 }
 
+impl<T, const S: usize, const W: usize> RectGrid<T> for ArrayGrid<T, S, W> {}
+
+impl<T, const S: usize, const W: usize> ColumnGrid<T> for ArrayGrid<T, S, W> {
+    fn column(&self, x: usize) -> Option<StepBy<Iter<'_, T>>> {
+        if x >= W {
+            return None;
+        }
+
+        Some(self.data[x..].iter().step_by(W))
+    }
+
+    fn column_mut(&mut self, x: usize) -> Option<StepBy<IterMut<'_, T>>> {
+        if x >= W {
+            return None;
+        }
+
+        Some(self.data[x..].iter_mut().step_by(W))
+    }
+}
+
 impl<T, const S: usize, const W: usize> IterableSliceGrid<T> for ArrayGrid<T, S, W> {
     fn cells(&self) -> SliceIter<'_, T> {
         SliceIter::new(&self.data, W)
@@ -246,7 +277,7 @@ impl<T> GetterMutGrid<T> for VecGrid<T> {
 
 impl<T> RowGrid<T> for VecGrid<T> {
     fn row(&self, y: usize) -> Option<&[T]> {
-        if y * self.width > self.data.len() {
+        if y * self.width >= self.data.len() {
             return None;
         }
 
@@ -254,7 +285,7 @@ impl<T> RowGrid<T> for VecGrid<T> {
     }
 
     fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
-        if y * self.width > self.data.len() {
+        if y * self.width >= self.data.len() {
             return None;
         }
 
@@ -262,6 +293,26 @@ impl<T> RowGrid<T> for VecGrid<T> {
     }
 }
 
+impl<T> RectGrid<T> for VecGrid<T> {}
+
+impl<T> ColumnGrid<T> for VecGrid<T> {
+    fn column(&self, x: usize) -> Option<StepBy<Iter<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+
+        Some(self.data[x..].iter().step_by(self.width))
+    }
+
+    fn column_mut(&mut self, x: usize) -> Option<StepBy<IterMut<'_, T>>> {
+        if x >= self.width {
+            return None;
+        }
+
+        Some(self.data[x..].iter_mut().step_by(self.width))
+    }
+}
+
 impl<T> IterableSliceGrid<T> for VecGrid<T> {
     fn cells(&self) -> SliceIter<'_, T> {
         return SliceIter::new(&self.data, self.width);
@@ -286,6 +337,96 @@ pub trait RowGrid<T> {
     fn row_mut(&mut self, y: usize) -> Option<&mut [T]>;
 }
 
+pub trait ColumnGrid<T> {
+    fn column(&self, x: usize) -> Option<StepBy<Iter<'_, T>>>;
+    fn column_mut(&mut self, x: usize) -> Option<StepBy<IterMut<'_, T>>>;
+}
+
+/// RayGrid has line-of-sight iteration for every grid with a size. Directions are given as
+/// offsets, so `Point(1, -1)` goes up and to the right.
+pub trait RayGrid<T>: GetterGrid<T> + FixedGrid + Sized {
+    /// Cast a ray from `p` (not included) in `dir` until it leaves the grid.
+    fn ray(&self, p: &Point<usize>, dir: &Point<isize>) -> Ray<'_, Self, T> {
+        let mut ray = Ray::new(self, *p, *dir);
+        ray.next = ray.step(p);
+        ray
+    }
+
+    /// Get the two diagonals through `p` from edge to edge. The first one goes down and to
+    /// the right, the second one goes down and to the left. They're empty if `p` is outside.
+    fn diagonals(&self, p: &Point<usize>) -> [Ray<'_, Self, T>; 2] {
+        if p.0 >= self.width() || p.1 >= self.height() {
+            return [Ray::empty(self, Point(1, 1)), Ray::empty(self, Point(-1, 1))];
+        }
+
+        let back = p.0.min(p.1);
+        let forward = (self.width() - 1 - p.0).min(p.1);
+
+        [
+            Ray::new(self, Point(p.0 - back, p.1 - back), Point(1, 1)),
+            Ray::new(self, Point(p.0 + forward, p.1 - forward), Point(-1, 1)),
+        ]
+    }
+
+    /// Look from `p` in `dir` until a cell that `blocks` is found. It returns the distance
+    /// travelled, including the blocking cell, and the blocking cell's position if one was
+    /// found before the edge.
+    fn first_visible<F>(&self, p: &Point<usize>, dir: &Point<isize>, blocks: F) -> (usize, Option<Point<usize>>)
+        where F: Fn(&T) -> bool {
+        let mut distance = 0;
+        for (pos, v) in self.ray(p, dir) {
+            distance += 1;
+            if blocks(v) {
+                return (distance, Some(pos));
+            }
+        }
+
+        (distance, None)
+    }
+}
+
+impl<G, T> RayGrid<T> for G where G: GetterGrid<T> + FixedGrid {}
+
+pub struct Ray<'g, G, T> {
+    grid: &'g G,
+    next: Option<Point<usize>>,
+    dir: Point<isize>,
+    spooky_ghost: PhantomData<T>,
+}
+
+impl<'g, G, T> Ray<'g, G, T> where G: GetterGrid<T> + FixedGrid {
+    fn new(grid: &'g G, start: Point<usize>, dir: Point<isize>) -> Self {
+        Ray { grid, next: Some(start), dir, spooky_ghost: PhantomData }
+    }
+
+    fn empty(grid: &'g G, dir: Point<isize>) -> Self {
+        Ray { grid, next: None, dir, spooky_ghost: PhantomData }
+    }
+
+    #[inline]
+    fn step(&self, p: &Point<usize>) -> Option<Point<usize>> {
+        let x = p.0.checked_add_signed(self.dir.0)?;
+        let y = p.1.checked_add_signed(self.dir.1)?;
+
+        if x < self.grid.width() && y < self.grid.height() {
+            Some(Point(x, y))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'g, G, T> Iterator for Ray<'g, G, T> where G: GetterGrid<T> + FixedGrid, T: 'g {
+    type Item = (Point<usize>, &'g T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = self.step(&current);
+
+        self.grid.get(&current).map(|v| (current, v))
+    }
+}
+
 pub trait NeighborCountGrid<T> {
     fn count_neighbors(&self, pos: &Point<usize>, pred: &T) -> usize;
     fn count_neighbors_where<F>(&self, pos: &Point<usize>, pred: F) -> usize where F: Fn(&T) -> bool;
@@ -341,6 +482,136 @@ pub trait FillableGrid<T> where T: Copy {
     fn fill(&mut self, v: T);
 }
 
+/// RectGrid has bulk operations on rectangles, where the rect's second point is exclusive, and on
+/// spans of a single row. They return false or `None` without touching the grid if the rect or
+/// span is empty or not inside the grid.
+pub trait RectGrid<T>: RowGrid<T> + FixedGrid {
+    fn map_rect<F>(&mut self, rect: &Rect<usize>, mut f: F) -> bool where F: FnMut(&mut T, Point<usize>) {
+        if !rect_inside(self, rect) {
+            return false;
+        }
+
+        let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+        for y in y1..y2 {
+            self.map_span(y, x1..x2, &mut f);
+        }
+
+        true
+    }
+
+    fn fill_rect(&mut self, rect: &Rect<usize>, v: T) -> bool where T: Copy {
+        if !rect_inside(self, rect) {
+            return false;
+        }
+
+        let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+        for y in y1..y2 {
+            self.fill_span(y, x1..x2, v);
+        }
+
+        true
+    }
+
+    fn toggle_rect(&mut self, rect: &Rect<usize>) -> bool where T: Not<Output=T> + Copy {
+        self.map_rect(rect, |v, _| *v = !*v)
+    }
+
+    fn count_rect<F>(&self, rect: &Rect<usize>, pred: F) -> Option<usize> where F: Fn(&T) -> bool {
+        if !rect_inside(self, rect) {
+            return None;
+        }
+
+        let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+        (y1..y2).map(|y| self.count_span(y, x1..x2, &pred)).sum()
+    }
+
+    fn map_span<F>(&mut self, y: usize, xs: Range<usize>, mut f: F) -> bool where F: FnMut(&mut T, Point<usize>) {
+        let x1 = xs.start;
+        match self.span_mut(y, xs) {
+            Some(span) => {
+                for (i, v) in span.iter_mut().enumerate() {
+                    f(v, Point(x1 + i, y));
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn fill_span(&mut self, y: usize, xs: Range<usize>, v: T) -> bool where T: Copy {
+        match self.span_mut(y, xs) {
+            Some(span) => {
+                span.fill(v);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn toggle_span(&mut self, y: usize, xs: Range<usize>) -> bool where T: Not<Output=T> + Copy {
+        self.map_span(y, xs, |v, _| *v = !*v)
+    }
+
+    fn count_span<F>(&self, y: usize, xs: Range<usize>, pred: F) -> Option<usize> where F: Fn(&T) -> bool {
+        if xs.is_empty() {
+            return None;
+        }
+
+        self.row(y)?.get(xs).map(|span| span.iter().filter(|v| pred(v)).count())
+    }
+
+    fn span_mut(&mut self, y: usize, xs: Range<usize>) -> Option<&mut [T]> {
+        if xs.is_empty() {
+            return None;
+        }
+
+        self.row_mut(y)?.get_mut(xs)
+    }
+}
+
+/// SummedAreaTable has the sum of every rectangle from the top left corner of a grid, which
+/// makes the sum of any rectangle four lookups.
+pub struct SummedAreaTable<T> {
+    sums: VecGrid<T>,
+}
+
+impl<T> SummedAreaTable<T> where T: Copy + Zero + Add<Output=T> + Sub<Output=T> {
+    pub fn new<G>(grid: &G) -> Self where G: GetterGrid<T> + FixedGrid {
+        // The sums have an extra row and column of zeroes at the top and left.
+        let width = grid.width() + 1;
+        let mut sums = VecGrid::new_with(width, grid.height() + 1, T::zero());
+        for y in 0..grid.height() {
+            let mut row_sum = T::zero();
+            for x in 0..grid.width() {
+                row_sum = row_sum + *grid.get(&Point(x, y)).unwrap();
+                let above = *sums.get(&Point(x + 1, y)).unwrap();
+                *sums.get_mut(&Point(x + 1, y + 1)).unwrap() = above + row_sum;
+            }
+        }
+
+        Self { sums }
+    }
+
+    /// Get the sum of the values in the rect, or `None` if it's empty or not inside the grid.
+    pub fn sum(&self, rect: &Rect<usize>) -> Option<T> {
+        let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+        if x1 >= x2 || y1 >= y2 || x2 >= self.sums.width() || y2 >= self.sums.height() {
+            return None;
+        }
+
+        let at = |x, y| *self.sums.get(&Point(x, y)).unwrap();
+        Some(at(x2, y2) + at(x1, y1) - at(x1, y2) - at(x2, y1))
+    }
+}
+
+#[inline]
+fn rect_inside<G: FixedGrid + ?Sized>(grid: &G, rect: &Rect<usize>) -> bool {
+    let Rect(Point(x1, y1), Point(x2, y2)) = *rect;
+
+    x1 < x2 && y1 < y2 && x2 <= grid.width() && y2 <= grid.height()
+}
+
 pub struct SliceIter<'a, T> {
     data: &'a [T],
     pos: usize,
@@ -350,7 +621,7 @@ pub struct SliceIter<'a, T> {
 }
 
 impl<'a, T> SliceIter<'a, T> {
-    fn new(data: &'a [T], width: usize) -> Self {
+    pub(crate) fn new(data: &'a [T], width: usize) -> Self {
         SliceIter {
             data,
             width,
@@ -498,6 +769,279 @@ impl<'i, G, T> GetterGrid<T> for SubGrid<'i, G> where G: GetterGrid<T> {
     }
 }
 
+/// SubGridMut is a mutable window into another grid. Positions outside the window are `None`
+/// even if the super grid has them.
+pub struct SubGridMut<'i, G> {
+    super_grid: &'i mut G,
+    offset: Point<usize>,
+    width: usize,
+    height: usize,
+}
+
+impl<'i, G> SubGridMut<'i, G> {
+    pub fn super_pos(&self, p: &Point<usize>) -> Point<usize> {
+        self.offset + *p
+    }
+
+    pub fn new(super_grid: &'i mut G, offset: Point<usize>, width: usize, height: usize) -> Self {
+        Self { super_grid, offset, width, height }
+    }
+
+    #[inline]
+    fn inside(&self, p: &Point<usize>) -> bool {
+        p.0 < self.width && p.1 < self.height
+    }
+}
+
+impl<'i, G> FixedGrid for SubGridMut<'i, G> {
+    #[inline]
+    fn width(&self) -> usize { self.width }
+    #[inline]
+    fn height(&self) -> usize { self.height }
+}
+
+impl<'i, G, T> GetterGrid<T> for SubGridMut<'i, G> where G: GetterGrid<T> {
+    fn get(&self, pos: &Point<usize>) -> Option<&T> {
+        if !self.inside(pos) {
+            return None;
+        }
+
+        self.super_grid.get(&(self.offset + *pos))
+    }
+}
+
+impl<'i, G, T> GetterMutGrid<T> for SubGridMut<'i, G> where G: GetterMutGrid<T> {
+    fn get_mut(&mut self, pos: &Point<usize>) -> Option<&mut T> {
+        if !self.inside(pos) {
+            return None;
+        }
+
+        self.super_grid.get_mut(&(self.offset + *pos))
+    }
+}
+
+impl<'i, G, T> RowGrid<T> for SubGridMut<'i, G> where G: RowGrid<T> {
+    fn row(&self, y: usize) -> Option<&[T]> {
+        if y >= self.height {
+            return None;
+        }
+
+        self.super_grid.row(self.offset.1 + y)
+            .and_then(|row| row.get(self.offset.0..self.offset.0 + self.width))
+    }
+
+    fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        if y >= self.height {
+            return None;
+        }
+
+        let (x, width) = (self.offset.0, self.width);
+        self.super_grid.row_mut(self.offset.1 + y)
+            .and_then(|row| row.get_mut(x..x + width))
+    }
+}
+
+/// TileGrid cuts a grid into tiles of equal size, where tile `Point(1, 0)` starts at
+/// `Point(w, 0)`. The tiles on the right and bottom edges are cut short if the grid size isn't
+/// a multiple of the tile size.
+pub trait TileGrid<T>: GetterGrid<T> + FixedGrid + Sized {
+    fn tiles(&self, w: usize, h: usize) -> Tiles<'_, Self, T> {
+        assert!(w > 0 && h > 0);
+
+        Tiles { grid: self, w, h, next: Point(0, 0), void: None }
+    }
+
+    fn tile(&self, index: &Point<usize>, w: usize, h: usize) -> Option<SubGrid<'_, Self>> {
+        let (offset, width, height) = tile_bounds(self, index, w, h)?;
+        Some(SubGrid::new(self, offset, width, height))
+    }
+
+    fn tile_mut(&mut self, index: &Point<usize>, w: usize, h: usize) -> Option<SubGridMut<'_, Self>> {
+        let (offset, width, height) = tile_bounds(self, index, w, h)?;
+        Some(SubGridMut::new(self, offset, width, height))
+    }
+}
+
+impl<G, T> TileGrid<T> for G where G: GetterGrid<T> + FixedGrid {}
+
+fn tile_bounds<G: FixedGrid>(grid: &G, index: &Point<usize>, w: usize, h: usize) -> Option<(Point<usize>, usize, usize)> {
+    let offset = Point(index.0 * w, index.1 * h);
+    if offset.0 >= grid.width() || offset.1 >= grid.height() {
+        return None;
+    }
+
+    Some((offset, w.min(grid.width() - offset.0), h.min(grid.height() - offset.1)))
+}
+
+pub struct Tiles<'g, G, T> {
+    grid: &'g G,
+    w: usize,
+    h: usize,
+    next: Point<usize>,
+    void: Option<&'g T>,
+}
+
+impl<'g, G, T> Tiles<'g, G, T> {
+    /// Skip the tiles where every cell is equal to `void`.
+    pub fn void(self, void: &'g T) -> Self {
+        Self { void: Some(void), ..self }
+    }
+}
+
+impl<'g, G, T> Iterator for Tiles<'g, G, T> where G: GetterGrid<T> + FixedGrid, T: Eq {
+    type Item = (Point<usize>, SubGrid<'g, G>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let index = self.next;
+            let tile = match self.grid.tile(&index, self.w, self.h) {
+                Some(tile) => tile,
+                None if index.0 == 0 => return None,
+                None => {
+                    self.next = Point(0, index.1 + 1);
+                    continue;
+                }
+            };
+
+            self.next = Point(index.0 + 1, index.1);
+
+            if let Some(void) = self.void {
+                let all_void = (0..tile.height())
+                    .flat_map(|y| (0..tile.width()).map(move |x| Point(x, y)))
+                    .all(|p| tile.get(&p) == Some(void));
+                if all_void {
+                    continue;
+                }
+            }
+
+            return Some((index, tile));
+        }
+    }
+}
+
+/// Wrapping is a toroidal view of a grid, where coordinates past any edge wrap around to the
+/// opposite side. With an inset, the outer border of that thickness is left out, which is useful
+/// for puzzles where the walls are part of the input but things wrap around inside them.
+pub struct Wrapping<G> {
+    grid: G,
+    inset: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<G> Wrapping<G> where G: FixedGrid {
+    pub fn new(grid: G) -> Self {
+        Self::with_inset(grid, 0)
+    }
+
+    pub fn with_inset(grid: G, inset: usize) -> Self {
+        assert!(grid.width() > inset * 2);
+        assert!(grid.height() > inset * 2);
+
+        Self {
+            width: grid.width() - inset * 2,
+            height: grid.height() - inset * 2,
+            grid,
+            inset,
+        }
+    }
+
+    pub fn inner(&self) -> &G {
+        &self.grid
+    }
+
+    pub fn inner_mut(&mut self) -> &mut G {
+        &mut self.grid
+    }
+
+    pub fn into_inner(self) -> G {
+        self.grid
+    }
+
+    /// Get the position in the underlying grid.
+    #[inline]
+    pub fn super_pos(&self, p: &Point<usize>) -> Point<usize> {
+        Point(
+            (p.0 % self.width) + self.inset,
+            (p.1 % self.height) + self.inset,
+        )
+    }
+
+    /// Wrap a signed position into the grid.
+    #[inline]
+    pub fn wrap(&self, p: &Point<isize>) -> Point<usize> {
+        Point(
+            p.0.rem_euclid(self.width as isize) as usize,
+            p.1.rem_euclid(self.height as isize) as usize,
+        )
+    }
+
+    /// Where you end up after moving `steps` times in `dir` from `p`, like a blizzard.
+    #[inline]
+    pub fn offset(&self, p: &Point<usize>, dir: &Point<isize>, steps: isize) -> Point<usize> {
+        let w = self.width as isize;
+        let h = self.height as isize;
+
+        Point(
+            ((p.0 % self.width) as isize + (steps.rem_euclid(w) * dir.0).rem_euclid(w)).rem_euclid(w) as usize,
+            ((p.1 % self.height) as isize + (steps.rem_euclid(h) * dir.1).rem_euclid(h)).rem_euclid(h) as usize,
+        )
+    }
+
+    /// The cardinals in the same order as `Point::cardinals`.
+    #[inline]
+    pub fn cardinals(&self, p: &Point<usize>) -> [Point<usize>; 4] {
+        let p = Point(p.0 % self.width, p.1 % self.height);
+        p.cardinals_wrapping(self.width - 1, self.height - 1)
+    }
+
+    /// The eight neighbors in the same order as `Point::neighbors`.
+    #[inline]
+    pub fn neighbors(&self, p: &Point<usize>) -> [Point<usize>; 8] {
+        let p = Point(p.0 as isize, p.1 as isize);
+        p.neighbors().map(|n| self.wrap(&n))
+    }
+
+    /// Cast a ray from `p` (not included) in direction `dir`. It stops before it gets back to `p`.
+    pub fn ray<'g, T>(&'g self, p: &Point<usize>, dir: &Point<isize>) -> impl Iterator<Item=(Point<usize>, &'g T)> + 'g
+        where G: GetterGrid<T>, T: 'g {
+        let start = Point(p.0 % self.width, p.1 % self.height);
+        let dir = *dir;
+        let mut current = start;
+
+        std::iter::from_fn(move || {
+            current = self.offset(&current, &dir, 1);
+            if current == start {
+                None
+            } else {
+                Some((current, self.get(&current).unwrap()))
+            }
+        })
+    }
+}
+
+impl<G> FixedGrid for Wrapping<G> {
+    #[inline]
+    fn width(&self) -> usize { self.width }
+    #[inline]
+    fn height(&self) -> usize { self.height }
+}
+
+impl<G, T> GetterGrid<T> for Wrapping<G> where G: GetterGrid<T> + FixedGrid {
+    #[inline]
+    fn get(&self, pos: &Point<usize>) -> Option<&T> {
+        self.grid.get(&self.super_pos(pos))
+    }
+}
+
+impl<G, T> GetterMutGrid<T> for Wrapping<G> where G: GetterMutGrid<T> + FixedGrid {
+    #[inline]
+    fn get_mut(&mut self, pos: &Point<usize>) -> Option<&mut T> {
+        let pos = self.super_pos(pos);
+        self.grid.get_mut(&pos)
+    }
+}
+
 pub fn render_char_grid<G, T>(grid: &G) -> String
     where G: GetterGrid<T> + FixedGrid,
           T: Into<char> + Copy {
@@ -570,4 +1114,118 @@ mod tests {
         assert_eq!(ag.cells().find(|(Point(x, y), i)| **i == 112 && *x == 11 && *y == 0).is_some(), true);
         assert_eq!(ag.cells().find(|(Point(x, y), i)| **i == 175 && *x == 10 && *y == 1).is_some(), false);
     }
+
+    #[test]
+    fn test_rect_grid() {
+        let mut grid: VecGrid<u8> = VecGrid::new_with(5, 4, b'.');
+        assert_eq!(grid.fill_rect(&Rect(Point(1, 1), Point(4, 3)), b'#'), true);
+        assert_eq!(grid.map_rect(&Rect(Point(0, 0), Point(2, 2)), |v, p| if p.0 == p.1 { *v = b'x' }), true);
+        assert_eq!(grid.fill_rect(&Rect(Point(3, 3), Point(6, 4)), b'#'), false);
+        assert_eq!(grid.fill_rect(&Rect(Point(3, 3), Point(3, 4)), b'#'), false);
+        assert_eq!(render_char_grid(&grid), "x....\n.x##.\n.###.\n.....\n");
+    }
+
+    #[test]
+    fn test_rect_counts_and_spans() {
+        let mut grid: VecGrid<bool> = VecGrid::new(6, 4);
+        assert!(grid.toggle_rect(&Rect(Point(1, 1), Point(4, 3))));
+        assert!(grid.toggle_span(1, 3..6));
+        assert!(grid.fill_span(3, 0..2, true));
+        assert!(!grid.fill_span(3, 5..7, true));
+        assert!(!grid.toggle_span(4, 0..1));
+
+        assert_eq!(render_grid(&grid, |v| (if *v { '#' } else { '.' }, None)), "......\n.##.##\n.###..\n##....\n");
+        assert_eq!(grid.count_rect(&Rect(Point(0, 0), Point(6, 4)), |v| *v), Some(9));
+        assert_eq!(grid.count_rect(&Rect(Point(2, 1), Point(4, 3)), |v| !*v), Some(1));
+        assert_eq!(grid.count_rect(&Rect(Point(2, 1), Point(7, 3)), |v| *v), None);
+        assert_eq!(grid.count_span(2, 0..4, |v| *v), Some(3));
+
+        let mut n = 0;
+        let numbers = VecGrid::new_from(4, (0..12).map(|_| { n += 1; n }).collect::<Vec<u32>>());
+        let table = SummedAreaTable::new(&numbers);
+        assert_eq!(table.sum(&Rect(Point(0, 0), Point(4, 3))), Some(78));
+        assert_eq!(table.sum(&Rect(Point(1, 1), Point(3, 3))), Some(6 + 7 + 10 + 11));
+        assert_eq!(table.sum(&Rect(Point(3, 2), Point(4, 3))), Some(12));
+        assert_eq!(table.sum(&Rect(Point(3, 2), Point(5, 3))), None);
+        assert_eq!(table.sum(&Rect(Point(3, 2), Point(3, 3))), None);
+    }
+
+    #[test]
+    fn test_columns_and_rays() {
+        let mut grid = VecGrid::parse_lines(b"abcd\nefgh\nijkl\n", b'\n').unwrap();
+        assert_eq!(grid.column(1).unwrap().copied().collect::<Vec<u8>>(), b"bfj".to_vec());
+        assert_eq!(grid.column(4).is_none(), true);
+        for v in grid.column_mut(3).unwrap() {
+            *v = b'#';
+        }
+        assert_eq!(grid.row(1), Some(b"efg#".as_slice()));
+
+        let ray = |p: Point<usize>, dir: Point<isize>| grid.ray(&p, &dir).map(|(_, v)| *v).collect::<Vec<u8>>();
+        assert_eq!(ray(Point(0, 0), Point(1, 0)), b"bc#".to_vec());
+        assert_eq!(ray(Point(2, 2), Point(-1, -1)), b"fa".to_vec());
+        assert_eq!(ray(Point(0, 2), Point(0, 1)), b"".to_vec());
+
+        let [down_right, down_left] = grid.diagonals(&Point(2, 1));
+        assert_eq!(down_right.map(|(_, v)| *v).collect::<Vec<u8>>(), b"bg#".to_vec());
+        assert_eq!(down_left.map(|(p, _)| p).collect::<Vec<_>>(), vec![Point(3, 0), Point(2, 1), Point(1, 2)]);
+        for p in [Point(4, 1), Point(1, 3)] {
+            let [down_right, down_left] = grid.diagonals(&p);
+            assert_eq!((down_right.count(), down_left.count()), (0, 0));
+            assert_eq!(grid.ray(&p, &Point(1, 1)).count(), 0);
+        }
+
+        assert_eq!(grid.first_visible(&Point(0, 1), &Point(1, 0), |v| *v == b'#'), (3, Some(Point(3, 1))));
+        assert_eq!(grid.first_visible(&Point(0, 1), &Point(1, 0), |v| *v == b'x'), (3, None));
+        assert_eq!(grid.first_visible(&Point(0, 1), &Point(-1, 0), |_| true), (0, None));
+    }
+
+    #[test]
+    fn test_tiles() {
+        let mut grid = VecGrid::parse_lines(b"..ab\n..cd\nef..\ngh..\nij..\n", b'\n').unwrap();
+
+        let tiles: Vec<_> = grid.tiles(2, 2).void(&b'.')
+            .map(|(index, tile)| (index, render_char_grid(&tile)))
+            .collect();
+        assert_eq!(tiles, vec![
+            (Point(1, 0), "ab\ncd\n".to_string()),
+            (Point(0, 1), "ef\ngh\n".to_string()),
+            (Point(0, 2), "ij\n".to_string()),
+        ]);
+        assert_eq!(grid.tiles(2, 2).count(), 6);
+        assert_eq!(grid.tiles(3, 3).map(|(_, t)| (t.width(), t.height())).collect::<Vec<_>>(), vec![(3, 3), (1, 3), (3, 2), (1, 2)]);
+
+        let mut tile = grid.tile_mut(&Point(1, 1), 2, 2).unwrap();
+        assert_eq!(tile.get(&Point(2, 0)), None);
+        *tile.get_mut(&Point(1, 1)).unwrap() = b'x';
+        tile.row_mut(0).unwrap().copy_from_slice(b"yz");
+        assert_eq!(tile.row(1), Some(&b".x"[..]));
+        assert_eq!(render_char_grid(&grid), "..ab\n..cd\nefyz\ngh.x\nij..\n");
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let grid = VecGrid::parse_lines(b"#####\n#abc#\n#def#\n#####\n", b'\n').unwrap();
+        let mut wrapping = Wrapping::with_inset(grid, 1);
+        assert_eq!(wrapping.width(), 3);
+        assert_eq!(wrapping.height(), 2);
+        assert_eq!(wrapping.get(&Point(0, 0)), Some(&b'a'));
+        assert_eq!(wrapping.get(&Point(4, 3)), Some(&b'e'));
+        assert_eq!(wrapping.wrap(&Point(-1, -1)), Point(2, 1));
+        assert_eq!(wrapping.offset(&Point(0, 0), &Point(-1, 0), 4), Point(2, 0));
+        assert_eq!(wrapping.offset(&Point(2, 1), &Point(1, 1), 1000), Point(0, 1));
+        assert_eq!(wrapping.offset(&Point(0, 0), &Point(-1, 1), isize::MAX), Point(2, 1));
+        assert_eq!(wrapping.cardinals(&Point(0, 0)), [Point(0, 1), Point(2, 0), Point(1, 0), Point(0, 1)]);
+        assert_eq!(wrapping.neighbors(&Point(0, 0))[0], Point(2, 1));
+        assert_eq!(
+            wrapping.ray(&Point(1, 0), &Point(1, 0)).map(|(_, v)| *v).collect::<Vec<u8>>(),
+            b"ca".to_vec(),
+        );
+        assert_eq!(
+            wrapping.ray(&Point(0, 0), &Point(1, 1)).map(|(p, _)| p).collect::<Vec<_>>(),
+            vec![Point(1, 1), Point(2, 0), Point(0, 1), Point(1, 0), Point(2, 1)],
+        );
+
+        *wrapping.get_mut(&Point(5, 1)).unwrap() = b'F';
+        assert_eq!(wrapping.inner().row(2), Some(b"#deF#".as_slice()));
+    }
 }
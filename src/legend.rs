@@ -0,0 +1,185 @@
+use std::fmt::Write;
+use crate::geo::Point;
+use crate::grid2::{FixedGrid, GetterGrid, VecGrid};
+
+/// Legend maps between the symbols in puzzle text and grid cell values in both directions.
+pub trait Legend<T> {
+    fn value(&self, symbol: u8) -> Option<T>;
+    fn symbol(&self, value: &T) -> Option<u8>;
+
+    /// Parse a grid, one line per row. Empty lines before and after the grid are skipped so that
+    /// fixtures can start on their own line. It returns `None` on unknown symbols or ragged lines.
+    fn parse_grid(&self, data: &[u8]) -> Option<VecGrid<T>> {
+        let mut lines: Vec<&[u8]> = data.split(|b| *b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .skip_while(|line| line.is_empty())
+            .collect();
+        while lines.last().map(|line| line.is_empty()).unwrap_or(false) {
+            lines.pop();
+        }
+
+        let width = lines.first()?.len();
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for line in lines {
+            if line.len() != width {
+                return None;
+            }
+
+            for b in line {
+                cells.push(self.value(*b)?);
+            }
+        }
+
+        Some(VecGrid::new_from(width, cells))
+    }
+
+    /// Render a grid with one line per row. Values without a symbol are rendered as `?`.
+    fn render_grid<G>(&self, grid: &G) -> String where G: GetterGrid<T> + FixedGrid {
+        let mut res = String::with_capacity((grid.width() + 1) * grid.height());
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                if let Some(v) = grid.get(&Point(x, y)) {
+                    res.push(self.symbol(v).unwrap_or(b'?') as char);
+                }
+            }
+
+            res.push('\n');
+        }
+
+        res
+    }
+}
+
+/// SymbolTable is a legend for plain values, like an enum without fields or a set of constants.
+/// The first entry of a value is used when rendering it.
+pub struct SymbolTable<T> {
+    entries: Vec<(u8, T)>,
+}
+
+impl<T> SymbolTable<T> where T: Copy + Eq {
+    pub fn new(entries: &[(u8, T)]) -> Self {
+        Self { entries: entries.to_vec() }
+    }
+}
+
+impl<T> Legend<T> for SymbolTable<T> where T: Copy + Eq {
+    fn value(&self, symbol: u8) -> Option<T> {
+        self.entries.iter().find(|(s, _)| *s == symbol).map(|(_, v)| *v)
+    }
+
+    fn symbol(&self, value: &T) -> Option<u8> {
+        self.entries.iter().find(|(_, v)| v == value).map(|(s, _)| *s)
+    }
+}
+
+/// Symbol is for cell types that know their own symbol, which fits types with data in them
+/// better than a table. Use it through the `Symbols` legend.
+pub trait Symbol: Sized {
+    fn from_symbol(symbol: u8) -> Option<Self>;
+    fn to_symbol(&self) -> u8;
+}
+
+pub struct Symbols;
+
+impl<T> Legend<T> for Symbols where T: Symbol {
+    fn value(&self, symbol: u8) -> Option<T> {
+        T::from_symbol(symbol)
+    }
+
+    fn symbol(&self, value: &T) -> Option<u8> {
+        Some(value.to_symbol())
+    }
+}
+
+/// Compare two rendered grids, and panic with both grids side by side if they differ. Rows that
+/// differ are marked with `>`, and the first differing column of each with `^`.
+#[track_caller]
+pub fn assert_grid_eq(actual: &str, expected: &str) {
+    let actual = actual.trim_matches('\n');
+    let expected = expected.trim_matches('\n');
+    if actual == expected {
+        return;
+    }
+
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let width = actual_lines.iter().map(|l| l.chars().count()).max().unwrap_or(0).max(6);
+
+    let mut msg = String::with_capacity((width * 2 + 8) * actual_lines.len().max(expected_lines.len()));
+    writeln!(msg, "grids differ:").unwrap();
+    writeln!(msg, "  {:<width$} | expected", "actual").unwrap();
+    for i in 0..actual_lines.len().max(expected_lines.len()) {
+        let a = actual_lines.get(i).copied().unwrap_or("");
+        let e = expected_lines.get(i).copied().unwrap_or("");
+        let marker = if a == e { ' ' } else { '>' };
+        writeln!(msg, "{} {:<width$} | {}", marker, a, e).unwrap();
+
+        if a != e {
+            let col = a.chars().zip(e.chars()).take_while(|(a, e)| a == e).count();
+            writeln!(msg, "  {:>col$}^", "").unwrap();
+        }
+    }
+
+    panic!("{}", msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid2::GetterMutGrid;
+    use super::*;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum Tile {
+        Open,
+        Wall,
+        Door(u8),
+    }
+
+    impl Symbol for Tile {
+        fn from_symbol(symbol: u8) -> Option<Self> {
+            match symbol {
+                b'.' => Some(Tile::Open),
+                b'#' => Some(Tile::Wall),
+                b'0'..=b'9' => Some(Tile::Door(symbol - b'0')),
+                _ => None,
+            }
+        }
+
+        fn to_symbol(&self) -> u8 {
+            match self {
+                Tile::Open => b'.',
+                Tile::Wall => b'#',
+                Tile::Door(n) => b'0' + *n,
+            }
+        }
+    }
+
+    #[test]
+    fn symbol_table_round_trip() {
+        let legend = SymbolTable::new(&[(b'.', 0u8), (b'#', 2), (b'o', 3), (b'~', 3)]);
+        let mut grid = legend.parse_grid(b"\n..#\n#~o\n\n").unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(&Point(1, 1)), Some(&3));
+        assert_grid_eq(&legend.render_grid(&grid), "..#\n#oo\n");
+
+        *grid.get_mut(&Point(0, 0)).unwrap() = 7;
+        assert_eq!(legend.render_grid(&grid), "?.#\n#oo\n");
+
+        assert!(legend.parse_grid(b"..#\n#x.\n").is_none());
+        assert!(legend.parse_grid(b"..#\n#.\n").is_none());
+    }
+
+    #[test]
+    fn symbols_round_trip() {
+        let grid: VecGrid<Tile> = Symbols.parse_grid(b"#.1\n2.#\n").unwrap();
+        assert_eq!(grid.get(&Point(2, 0)), Some(&Tile::Door(1)));
+        assert_eq!(Symbols.render_grid(&grid), "#.1\n2.#\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "> #.#    | ##.\n   ^")]
+    fn assert_grid_eq_shows_diff() {
+        assert_grid_eq("...\n#.#\n", "\n...\n##.\n");
+    }
+}
@@ -10,8 +10,15 @@ pub mod geo;
 pub mod octree;
 pub mod grid;
 pub mod grid2;
-pub mod search;
+pub mod voxel;
+pub mod cube_net;
+pub mod flood;
+pub mod render;
+pub mod legend;
+pub mod cycle;
 pub mod search2;
+pub mod memo;
+pub mod bisect;
 pub mod graph;
 #[cfg(feature = "2019")]
 pub mod intcode;
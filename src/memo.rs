@@ -0,0 +1,156 @@
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use rustc_hash::FxHashMap;
+use crate::search2::CostMap;
+
+/// Memo caches the values of a recursive function by its key. The values are kept in any
+/// `CostMap`, like a `FxHashMap` from `memo` or a `DenseMap` from `memo_with`.
+pub struct Memo<K, V, STORE> {
+    store: STORE,
+    limit: Option<usize>,
+    len: usize,
+    stats: MemoStats,
+    key: PhantomData<(K, V)>,
+}
+
+/// MemoStats are the counts of how the cache of a `Memo` has been used.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoStats {
+    /// The lookups that found a value.
+    pub hits: usize,
+    /// The lookups that had to run the function.
+    pub misses: usize,
+    /// The values that were dropped because the cache was full.
+    pub evictions: usize,
+}
+
+impl Display for MemoStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hits {}, misses {}, evictions {}", self.hits, self.misses, self.evictions)
+    }
+}
+
+impl<K, V, STORE> Memo<K, V, STORE> where K: Clone, V: Clone, STORE: CostMap<K, V> {
+    /// Empty the cache once it holds `limit` values, so that it doesn't grow without bounds.
+    /// Only use it when the values are cheap enough to compute again.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Get the value of the key, from the cache or by running `f`. The function gets a callback
+    /// to look up other keys through the same cache, which is how it recurses.
+    pub fn solve<F>(&mut self, key: K, f: F) -> V where F: Fn(&mut dyn FnMut(K) -> V, &K) -> V {
+        self.call(key, &f)
+    }
+
+    fn call<F>(&mut self, key: K, f: &F) -> V where F: Fn(&mut dyn FnMut(K) -> V, &K) -> V {
+        if let Some(value) = self.store.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+
+        self.stats.misses += 1;
+        let value = f(&mut |key| self.call(key, f), &key);
+
+        if self.limit.map(|limit| self.len >= limit).unwrap_or(false) {
+            self.stats.evictions += self.len;
+            self.store.clear();
+            self.len = 0;
+        }
+        if self.store.insert(key, value.clone()) {
+            self.len += 1;
+        }
+
+        value
+    }
+
+    /// Get a cached value without running anything.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.store.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.stats
+    }
+
+    pub fn clear(&mut self) {
+        self.store.clear();
+        self.len = 0;
+        self.stats = MemoStats::default();
+    }
+}
+
+/// Create a memo with a hashed cache.
+pub fn memo<K, V>() -> Memo<K, V, FxHashMap<K, V>> where K: Hash + Eq {
+    memo_with(FxHashMap::default())
+}
+
+/// Create a memo that keeps the values in `store`, like a `DenseMap` for keys that can be
+/// indexed. Keys that the store can't hold are not cached.
+pub fn memo_with<K, V, STORE>(store: STORE) -> Memo<K, V, STORE> where STORE: CostMap<K, V> {
+    Memo { store, limit: None, len: 0, stats: MemoStats::default(), key: PhantomData }
+}
+
+/// Fill a table bottom-up, where `f` gets the values of all the lower indices.
+pub fn tabulate<V, F>(len: usize, mut f: F) -> Vec<V> where F: FnMut(&[V], usize) -> V {
+    let mut table = Vec::with_capacity(len);
+    for i in 0..len {
+        let value = f(&table, i);
+        table.push(value);
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search2::{DenseMap, IndexFn};
+    use super::*;
+
+    fn fib(n: &u64, memo: &mut dyn FnMut(u64) -> u64) -> u64 {
+        if *n < 2 { *n } else { memo(n - 1) + memo(n - 2) }
+    }
+
+    #[test]
+    fn memoized_recursion() {
+        let mut hashed = memo();
+        assert_eq!(hashed.solve(90u64, |memo, n| fib(n, memo)), 2880067194370816120);
+        assert_eq!(hashed.stats(), MemoStats { hits: 88, misses: 91, evictions: 0 });
+        assert_eq!(hashed.get(&10), Some(&55));
+        assert_eq!(hashed.len(), 91);
+
+        hashed.solve(90, |memo, n| fib(n, memo));
+        assert_eq!(hashed.stats().hits, 89);
+
+        let mut dense = memo_with(DenseMap::new(IndexFn(91, |n: &u64| Some(*n as usize).filter(|n| *n <= 90))));
+        assert_eq!(dense.solve(90, |memo, n| fib(n, memo)), 2880067194370816120);
+        assert_eq!(dense.stats(), MemoStats { hits: 88, misses: 91, evictions: 0 });
+        assert_eq!(dense.solve(91, |memo, n| fib(n, memo)), 4660046610375530309);
+        assert_eq!(dense.len(), 91);
+
+        let mut limited = memo().limit(10);
+        assert_eq!(limited.solve(30u64, |memo, n| fib(n, memo)), 832040);
+        assert!(limited.len() <= 10 && limited.stats().evictions > 0);
+    }
+
+    #[test]
+    fn tabulated() {
+        // The ways to climb n stairs with steps of 1, 2 or 3.
+        let ways = tabulate(10, |table: &[u32], i| match i {
+            0 => 1,
+            _ => table[i.saturating_sub(3)..i].iter().sum(),
+        });
+
+        assert_eq!(ways, vec![1, 1, 2, 4, 7, 13, 24, 44, 81, 149]);
+    }
+}
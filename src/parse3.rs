@@ -3,6 +3,7 @@ use std::marker::PhantomData;
 use std::ops::{Neg, Range, RangeInclusive};
 use num::Integer;
 use crate::geo::{Point, Vertex};
+use crate::grid2::VecGrid;
 
 pub trait Parser<'i, T> {
     fn parse(&self, input: &'i [u8]) -> ParseResult<'i, T>;
@@ -1014,8 +1015,102 @@ pub fn vertex<'i, T: Copy + Default + 'i, P: Parser<'i, T> + Copy>(p: P) -> impl
         .map(|((x, y), z)| Vertex(x, y, z))
 }
 
+#[derive(Copy, Clone)]
+pub struct GridParser<T, F> {
+    cb: F,
+    fill: Option<T>,
+}
+
+impl<T, F> GridParser<T, F> where T: Copy, F: Fn(u8) -> Option<T> {
+    /// Accept lines shorter than the longest one, padding the rest of the row with `fill`.
+    pub fn padded(self, fill: T) -> Self {
+        Self { cb: self.cb, fill: Some(fill) }
+    }
+
+    /// Also collect the position of every cell whose byte is in `markers`, in reading order.
+    pub fn with_markers(self, markers: &[u8]) -> GridWithMarkers<'_, T, F> {
+        GridWithMarkers(self, markers)
+    }
+
+    fn parse_grid<'i>(&self, input: &'i [u8], markers: &[u8], found: &mut Vec<(u8, Point<usize>)>) -> ParseResult<'i, VecGrid<T>> {
+        let mut lines = Vec::with_capacity(64);
+        let mut pos = 0;
+        while pos < input.len() {
+            let line_len = input[pos..].iter().take_while(|b| **b != b'\n').count();
+            let line = &input[pos..pos + line_len];
+            pos = (pos + line_len + 1).min(input.len());
+
+            if line.is_empty() {
+                break;
+            }
+
+            lines.push(line);
+        }
+
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        if width == 0 {
+            return ParseResult::Bad(input);
+        }
+
+        let mut data = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() < width && self.fill.is_none() {
+                return ParseResult::Bad(input);
+            }
+
+            for (x, b) in line.iter().enumerate() {
+                if markers.contains(b) {
+                    found.push((*b, Point(x, y)));
+                }
+
+                match (self.cb)(*b) {
+                    Some(v) => data.push(v),
+                    None => return ParseResult::Bad(input),
+                }
+            }
+            if let Some(fill) = self.fill {
+                data.resize(width * (y + 1), fill);
+            }
+        }
+
+        ParseResult::Good(VecGrid::new_from(width, data), &input[pos..])
+    }
+}
+
+impl<'i, T, F> Parser<'i, VecGrid<T>> for GridParser<T, F> where T: Copy, F: Fn(u8) -> Option<T> {
+    fn parse(&self, input: &'i [u8]) -> ParseResult<'i, VecGrid<T>> {
+        self.parse_grid(input, &[], &mut Vec::new())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct GridWithMarkers<'m, T, F> (GridParser<T, F>, &'m [u8]);
+
+impl<'i, 'm, T, F> Parser<'i, (VecGrid<T>, Vec<(u8, Point<usize>)>)> for GridWithMarkers<'m, T, F> where T: Copy, F: Fn(u8) -> Option<T> {
+    fn parse(&self, input: &'i [u8]) -> ParseResult<'i, (VecGrid<T>, Vec<(u8, Point<usize>)>)> {
+        let mut found = Vec::new();
+        match self.0.parse_grid(input, self.1, &mut found) {
+            ParseResult::Good(grid, new_input) => ParseResult::Good((grid, found), new_input),
+            ParseResult::Bad(input) => ParseResult::Bad(input),
+        }
+    }
+}
+
+/// Parse lines of bytes into a grid, mapping each byte with `cb`. The grid ends at an empty
+/// line or the end of the input, and the empty line is consumed so that the following
+/// section can be parsed with `.and(...)`. Ragged lines are only accepted if `padded` is used.
+pub fn grid<T, F>(cb: F) -> GridParser<T, impl Fn(u8) -> Option<T>> where T: Copy, F: Fn(u8) -> T {
+    try_grid(move |b| Some(cb(b)))
+}
+
+/// Parse a grid like `grid`, but fail if `cb` returns `None` for any of the bytes.
+pub fn try_grid<T, F>(cb: F) -> GridParser<T, F> where T: Copy, F: Fn(u8) -> Option<T> {
+    GridParser { cb, fill: None }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::grid2::{FixedGrid, IterableSliceGrid, RowGrid};
     use super::*;
 
     #[test]
@@ -1233,6 +1328,41 @@ mod tests {
         assert_eq!(vertex(signed_int::<i32>()).parse(b"<19,39,23>"), ParseResult::Bad(b"<19,39,23>"));
     }
 
+    #[test]
+    fn test_grid() {
+        let (g, rest) = grid(|b| b).parse(b"#..\n.#.\n..#\n\nrest").unwrap_and_input();
+        assert_eq!(g.width(), 3);
+        assert_eq!(g.height(), 3);
+        assert_eq!(g.row(1), Some(b".#.".as_slice()));
+        assert_eq!(rest, b"rest");
+
+        assert!(matches!(grid(|b| b).parse(b"#..\n.#\n..#\n"), ParseResult::Bad(b"#..\n.#\n..#\n")));
+        assert!(matches!(grid(|b| b).parse(b"\n#..\n"), ParseResult::Bad(b"\n#..\n")));
+
+        let g = grid(|b| b).padded(b' ').parse(b"  ..\n.\n...\n").unwrap();
+        assert_eq!(g.width(), 4);
+        assert_eq!(g.height(), 3);
+        assert_eq!(g.row(1), Some(b".   ".as_slice()));
+
+        let parser = try_grid(|b| match b {
+            b'S' => Some(0u8),
+            b'E' => Some(25),
+            b'a'..=b'z' => Some(b - b'a'),
+            _ => None,
+        }).with_markers(b"SE");
+        let (g, markers) = parser.parse(b"Sab\nxzE").unwrap();
+        assert_eq!(g.row(0), Some([0u8, 0, 1].as_slice()));
+        assert_eq!(g.row(1), Some([23u8, 25, 25].as_slice()));
+        assert_eq!(markers, vec![(b'S', Point(0, 0)), (b'E', Point(2, 1))]);
+        assert!(matches!(parser.parse(b"Sab\r\nxzE\r\n"), ParseResult::Bad(_)));
+
+        let parser = grid(|b| b == b'#').and(unsigned_int::<u32>().repeat_delimited(b','));
+        let ((g, numbers), rest) = parser.parse(b"#.\n.#\n\n1,2,3\n").unwrap_and_input();
+        assert_eq!(g.cells().filter(|(_, v)| **v).count(), 2);
+        assert_eq!(numbers, vec![1u32, 2, 3]);
+        assert_eq!(rest, b"\n");
+    }
+
     #[test]
     fn test_quoted_by() {
         assert_eq!(
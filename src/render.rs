@@ -0,0 +1,318 @@
+use std::fmt::Write as FmtWrite;
+use std::io;
+use std::io::Write;
+use crate::geo::{Point, Rect};
+use crate::grid2::{FixedGrid, GetterGrid};
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+/// GridRenderer renders grids to colored terminal output or images. By default, it renders
+/// the whole grid without rulers at a scale of one pixel per cell.
+#[derive(Debug)]
+pub struct GridRenderer {
+    viewport: Option<Rect<usize>>,
+    rulers: bool,
+    scale: usize,
+}
+
+impl Default for GridRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GridRenderer {
+    pub fn new() -> Self {
+        Self { viewport: None, rulers: false, scale: 1 }
+    }
+
+    /// Only render the cells inside the rect. It is cropped to the grid.
+    pub fn viewport(self, rect: Rect<usize>) -> Self {
+        Self { viewport: Some(rect), ..self }
+    }
+
+    /// Add x coordinates above and y coordinates to the left of the grid in the terminal output.
+    pub fn rulers(self) -> Self {
+        Self { rulers: true, ..self }
+    }
+
+    /// Number of pixels per side of a cell in image output.
+    pub fn scale(self, scale: usize) -> Self {
+        assert!(scale > 0);
+        Self { scale, ..self }
+    }
+
+    /// Render the grid with ANSI color codes. The callback returns the character along with the
+    /// foreground and background color.
+    pub fn ansi<G, T, F>(&self, grid: &G, cb: F) -> String
+        where G: GetterGrid<T> + FixedGrid,
+              F: Fn(&T) -> (char, Option<Rgb>, Option<Rgb>) {
+        let Rect(Point(x1, y1), Point(x2, y2)) = self.crop(grid);
+        let margin = if self.rulers { digits(y2.saturating_sub(1)) + 1 } else { 0 };
+        let mut res = String::with_capacity((x2 - x1 + margin + 1) * (y2 - y1) * 2);
+
+        if self.rulers {
+            let rows = digits(x2.saturating_sub(1));
+            for i in (0..rows).rev() {
+                write!(res, "{:width$}", "", width = margin).unwrap();
+                for x in x1..x2 {
+                    let digit = (x / 10usize.pow(i as u32)) % 10;
+                    if digit == 0 && x < 10usize.pow(i as u32) && i > 0 {
+                        res.push(' ');
+                    } else {
+                        res.push((b'0' + digit as u8) as char);
+                    }
+                }
+                res.push('\n');
+            }
+        }
+
+        for y in y1..y2 {
+            if self.rulers {
+                write!(res, "{:>width$} ", y, width = margin - 1).unwrap();
+            }
+
+            let mut current = (None, None);
+            for x in x1..x2 {
+                let (ch, fg, bg) = cb(grid.get(&Point(x, y)).unwrap());
+                if (fg, bg) != current {
+                    if current != (None, None) {
+                        res.push_str("\x1b[0m");
+                    }
+                    if let Some(Rgb(r, g, b)) = fg {
+                        write!(res, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                    }
+                    if let Some(Rgb(r, g, b)) = bg {
+                        write!(res, "\x1b[48;2;{};{};{}m", r, g, b).unwrap();
+                    }
+                    current = (fg, bg);
+                }
+
+                res.push(ch);
+            }
+
+            if current != (None, None) {
+                res.push_str("\x1b[0m");
+            }
+            res.push('\n');
+        }
+
+        res
+    }
+
+    /// Write the grid as a binary PPM (P6) image.
+    pub fn ppm<G, T, F, W>(&self, grid: &G, cb: F, out: &mut W) -> io::Result<()>
+        where G: GetterGrid<T> + FixedGrid,
+              F: Fn(&T) -> Rgb,
+              W: Write {
+        let (width, height, pixels) = self.pixels(grid, cb);
+
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        for row in pixels.iter() {
+            out.write_all(row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the grid as an uncompressed PNG image.
+    pub fn png<G, T, F, W>(&self, grid: &G, cb: F, out: &mut W) -> io::Result<()>
+        where G: GetterGrid<T> + FixedGrid,
+              F: Fn(&T) -> Rgb,
+              W: Write {
+        let (width, height, pixels) = self.pixels(grid, cb);
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        // Every scanline is prefixed with filter type 0 (none).
+        let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+        for row in pixels.iter() {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(out, b"IHDR", &header)?;
+        write_png_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(out, b"IEND", &[])?;
+
+        Ok(())
+    }
+
+    fn crop<G: FixedGrid>(&self, grid: &G) -> Rect<usize> {
+        match &self.viewport {
+            Some(Rect(Point(x1, y1), Point(x2, y2))) => {
+                let x2 = (*x2).min(grid.width());
+                let y2 = (*y2).min(grid.height());
+                Rect(Point((*x1).min(x2), (*y1).min(y2)), Point(x2, y2))
+            }
+            None => Rect(Point(0, 0), Point(grid.width(), grid.height())),
+        }
+    }
+
+    fn pixels<G, T, F>(&self, grid: &G, cb: F) -> (usize, usize, Vec<Vec<u8>>)
+        where G: GetterGrid<T> + FixedGrid,
+              F: Fn(&T) -> Rgb {
+        let Rect(Point(x1, y1), Point(x2, y2)) = self.crop(grid);
+        let width = (x2 - x1) * self.scale;
+        let height = (y2 - y1) * self.scale;
+
+        let mut rows = Vec::with_capacity(height);
+        for y in y1..y2 {
+            let mut row = Vec::with_capacity(width * 3);
+            for x in x1..x2 {
+                let Rgb(r, g, b) = cb(grid.get(&Point(x, y)).unwrap());
+                for _ in 0..self.scale {
+                    row.extend_from_slice(&[r, g, b]);
+                }
+            }
+
+            for _ in 1..self.scale {
+                rows.push(row.clone());
+            }
+            rows.push(row);
+        }
+
+        (width, height, rows)
+    }
+}
+
+fn digits(mut n: usize) -> usize {
+    let mut count = 1;
+    while n >= 10 {
+        n /= 10;
+        count += 1;
+    }
+
+    count
+}
+
+fn write_png_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data.iter()));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap the data in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(data.len() + (data.len() / 65535 + 1) * 5 + 6);
+    res.extend_from_slice(&[0x78, 0x01]);
+
+    let mut chunks = data.chunks(65535).peekable();
+    if chunks.peek().is_none() {
+        res.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let len = chunk.len() as u16;
+        res.push(if chunks.peek().is_none() { 1 } else { 0 });
+        res.extend_from_slice(&len.to_le_bytes());
+        res.extend_from_slice(&(!len).to_le_bytes());
+        res.extend_from_slice(chunk);
+    }
+
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+fn crc32<'a, I: Iterator<Item=&'a u8>>(data: I) -> u32 {
+    let mut crc = 0xffffffffu32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for v in data {
+        a = (a + *v as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid2::VecGrid;
+    use super::*;
+
+    fn test_grid() -> VecGrid<u8> {
+        VecGrid::parse_lines(b"..#.........\n.##.........\n............\n", b'\n').unwrap()
+    }
+
+    #[test]
+    fn ansi_rulers_and_viewport() {
+        let grid = test_grid();
+        let plain = |v: &u8| (*v as char, None, None);
+
+        assert_eq!(GridRenderer::new().ansi(&grid, plain), "..#.........\n.##.........\n............\n");
+        assert_eq!(
+            GridRenderer::new().rulers().ansi(&grid, plain),
+            concat!(
+                "            11\n",
+                "  012345678901\n",
+                "0 ..#.........\n",
+                "1 .##.........\n",
+                "2 ............\n",
+            ),
+        );
+        assert_eq!(
+            GridRenderer::new().rulers().viewport(Rect(Point(1, 1), Point(4, 10))).ansi(&grid, plain),
+            "  123\n1 ##.\n2 ...\n",
+        );
+    }
+
+    #[test]
+    fn ansi_colors() {
+        let grid = test_grid();
+        let red = Rgb(255, 0, 0);
+
+        assert_eq!(
+            GridRenderer::new()
+                .viewport(Rect(Point(0, 1), Point(4, 2)))
+                .ansi(&grid, |v| if *v == b'#' { ('#', Some(red), None) } else { ('.', None, None) }),
+            ".\x1b[38;2;255;0;0m##\x1b[0m.\n",
+        );
+    }
+
+    #[test]
+    fn images() {
+        let grid = test_grid();
+        let color = |v: &u8| if *v == b'#' { Rgb(255, 255, 255) } else { Rgb(0, 0, 0) };
+
+        let mut ppm = Vec::new();
+        GridRenderer::new().scale(2).ppm(&grid, color, &mut ppm).unwrap();
+        assert_eq!(&ppm[..11], b"P6\n24 6\n255");
+        assert_eq!(ppm.len(), 12 + 24 * 6 * 3);
+        assert_eq!(&ppm[12 + 4 * 3..12 + 6 * 3], &[255, 255, 255, 255, 255, 255]);
+
+        let mut ppm = Vec::new();
+        GridRenderer::default().ppm(&grid, color, &mut ppm).unwrap();
+        assert_eq!(&ppm[..11], b"P6\n12 3\n255");
+
+        let mut png = Vec::new();
+        GridRenderer::new().png(&grid, color, &mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[16..24], &[0, 0, 0, 12, 0, 0, 0, 3]);
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789".iter()), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+}
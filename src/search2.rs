@@ -1,20 +1,72 @@
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 use std::collections::{BinaryHeap, VecDeque};
 use std::collections::hash_map::Entry;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::Add;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU64, AtomicU8, AtomicUsize};
+use std::sync::atomic::Ordering as AtomicOrdering;
+use num::traits::Zero;
+use rayon::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet};
+use crate::cycle::hash_state;
+use crate::geo::{Point, Vertex};
 
 pub trait Search<S: Sized>: Sized {
     fn reset(&mut self);
     fn next_step(&mut self) -> Option<S>;
-    fn add_step(&mut self, step: S);
+
+    /// Add a step, and return whether the search accepted it. It's not accepted if the state
+    /// has been seen before, or with a lower cost for dijkstra.
+    fn try_add_step(&mut self, step: S) -> bool;
+
+    /// Get the counts of what the search has done since it was created or reset.
+    fn stats(&self) -> SearchStats;
+
+    #[inline]
+    fn add_step(&mut self, step: S) {
+        self.try_add_step(step);
+    }
 
     /// Add a filter to the search that is applied on states before they're run. No state
     /// will be evaluated twice with the bounds check.
     fn bounded<F: Fn(&S) -> bool>(self, f: F) -> Bounded<Self, F> {
-        Bounded(self, f)
+        Bounded(self, f, 0)
+    }
+
+    /// Record the parent of every accepted state, so the path to any discovered state can be
+    /// looked up with `WithPaths::path_to`.
+    fn with_paths(self) -> WithPaths<Self, S, (), fn(&S)> where S: Hash + Eq + Clone {
+        WithPaths::new(self, |_| (), false)
+    }
+
+    /// Record every parent that reaches a state at its lowest cost, including the ones the
+    /// search rejects as duplicates, so that all the equally short paths can be listed with
+    /// `WithPaths::paths_to`. The cost must not depend on anything but the path to the state.
+    fn with_all_paths<C, F>(self, cost: F) -> WithPaths<Self, S, C, F> where S: Hash + Eq + Clone, C: PartialOrd, F: Fn(&S) -> C {
+        WithPaths::new(self, cost, true)
+    }
+
+    /// Keep a Pareto frontier of the states for each key, and skip the states that are dominated
+    /// by another state with the same key. `dominates(a, b)` should return whether `a` is at
+    /// least as good as `b` in every way, see `dominates` for comparing arrays of numbers.
+    fn pareto<K, FK, FD>(self, key: FK, dominates: FD) -> Pareto<Self, S, K, FK, FD> where K: Hash + Eq, FK: Fn(&S) -> K, FD: Fn(&S, &S) -> bool {
+        Pareto(self, key, dominates, FxHashMap::default(), 0)
+    }
+
+    /// Record the states that are goals and keep searching through them, so that every goal
+    /// that can be reached is listed in `Goals::goals` after one run.
+    fn collect_goals<FG>(self, is_goal: FG) -> Goals<Self, S, (), FG, fn(&S)> where S: Clone, FG: Fn(&S) -> bool {
+        Goals::new(self, is_goal, |_| (), false)
+    }
+
+    /// Record all the goals with the lowest cost instead of stopping at the first one. Goals end
+    /// their branch, so they're not returned by the search, and the states that cost more than
+    /// the best goal found are skipped. It needs a cost that never goes down along a path.
+    fn optimal_goals<C, FG, FC>(self, is_goal: FG, cost: FC) -> Goals<Self, S, C, FG, FC> where C: PartialOrd, FG: Fn(&S) -> bool, FC: Fn(&S) -> C {
+        Goals::new(self, is_goal, cost, true)
     }
 
     fn run<R, F: FnMut(&mut Self, &S) -> Option<R>>(self, f: F) -> Run<Self, F, S, R> {
@@ -22,8 +74,87 @@ pub trait Search<S: Sized>: Sized {
     }
 }
 
+/// A borrowed search can be run, so that it can be looked at after running it.
+impl<S, SEARCH> Search<S> for &mut SEARCH where SEARCH: Search<S> {
+    #[inline]
+    fn reset(&mut self) { (**self).reset() }
+    #[inline]
+    fn next_step(&mut self) -> Option<S> { (**self).next_step() }
+    #[inline]
+    fn try_add_step(&mut self, step: S) -> bool { (**self).try_add_step(step) }
+    #[inline]
+    fn stats(&self) -> SearchStats { (**self).stats() }
+}
+
+/// SearchStats are the counts of what a search has done.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchStats {
+    /// The states that were accepted into the search.
+    pub pushed: usize,
+    /// The states that were taken out of the search, including the ones that were skipped.
+    pub popped: usize,
+    /// The states that were rejected because they were seen, or dominated, before.
+    pub duplicates: usize,
+    /// The states that were skipped by `bounded`.
+    pub bounded: usize,
+    /// The most states that were waiting in the search at once.
+    pub peak_frontier: usize,
+}
+
+impl SearchStats {
+    #[inline]
+    fn push(&mut self, frontier: usize) {
+        self.pushed += 1;
+        self.peak_frontier = self.peak_frontier.max(frontier);
+    }
+
+    #[inline]
+    fn push_many(&mut self, count: usize, frontier: usize) {
+        self.pushed += count;
+        self.peak_frontier = self.peak_frontier.max(frontier);
+    }
+
+    /// Add the counts of another part of the same search, like another thread of it.
+    fn merge(&mut self, other: SearchStats) {
+        self.pushed += other.pushed;
+        self.popped += other.popped;
+        self.duplicates += other.duplicates;
+        self.bounded += other.bounded;
+        self.peak_frontier = self.peak_frontier.max(other.peak_frontier);
+    }
+
+    #[inline]
+    fn pop<S>(&mut self, step: Option<S>) -> Option<S> {
+        if step.is_some() {
+            self.popped += 1;
+        }
+
+        step
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f, "pushed {}, popped {}, duplicates {}, bounded {}, peak frontier {}",
+            self.pushed, self.popped, self.duplicates, self.bounded, self.peak_frontier,
+        )
+    }
+}
+
 pub struct Run<SEARCH, F, S, R> (SEARCH, F, PhantomData<(R, S)>);
 
+impl<SEARCH, F, S, R> Run<SEARCH, F, S, R> where SEARCH: Search<S> {
+    /// Get the stats of the search so far, which are the totals once the run is finished.
+    pub fn stats(&self) -> SearchStats {
+        self.0.stats()
+    }
+
+    pub fn search(&self) -> &SEARCH {
+        &self.0
+    }
+}
+
 impl<SEARCH, F, S, R> Iterator for Run<SEARCH, F, S, R>
     where SEARCH: Search<S>,
           F: FnMut(&mut SEARCH, &S) -> Option<R> {
@@ -41,257 +172,1925 @@ impl<SEARCH, F, S, R> Iterator for Run<SEARCH, F, S, R>
     }
 }
 
-pub struct Bounded<SEARCH, F> (SEARCH, F);
+pub struct Bounded<SEARCH, F> (SEARCH, F, usize);
 
 impl<S, SEARCH, F> Search<S> for Bounded<SEARCH, F>
     where SEARCH: Search<S>,
           F: Fn(&S) -> bool {
     #[inline]
-    fn reset(&mut self) { self.0.reset() }
+    fn reset(&mut self) {
+        self.0.reset();
+        self.2 = 0;
+    }
     fn next_step(&mut self) -> Option<S> {
-        if let Some(next_step) = self.0.next_step() {
+        while let Some(next_step) = self.0.next_step() {
             if self.1(&next_step) {
-                Some(next_step)
-            } else {
-                self.next_step()
+                return Some(next_step);
             }
-        } else {
-            None
+
+            self.2 += 1;
         }
+
+        None
     }
     #[inline]
-    fn add_step(&mut self, step: S) { self.0.add_step(step) }
+    fn try_add_step(&mut self, step: S) -> bool { self.0.try_add_step(step) }
+    fn stats(&self) -> SearchStats {
+        SearchStats { bounded: self.0.stats().bounded + self.2, ..self.0.stats() }
+    }
 }
 
-struct DFS<S> {
-    stack: Vec<S>,
-    seen: FxHashSet<S>,
-}
+pub struct Pareto<SEARCH, S, K, FK, FD> (SEARCH, FK, FD, FxHashMap<K, Vec<S>>, usize);
 
-impl<S> Search<S> for DFS<S> where S: Hash + Eq + Clone {
+impl<S, SEARCH, K, FK, FD> Search<S> for Pareto<SEARCH, S, K, FK, FD>
+    where SEARCH: Search<S>,
+          S: Clone,
+          K: Hash + Eq,
+          FK: Fn(&S) -> K,
+          FD: Fn(&S, &S) -> bool {
     fn reset(&mut self) {
-        self.stack.clear();
-        self.seen.clear();
+        self.0.reset();
+        self.3.clear();
+        self.4 = 0;
     }
 
     fn next_step(&mut self) -> Option<S> {
-        self.stack.pop()
+        // States that were added before a state that dominates them are still in the search.
+        while let Some(step) = self.0.next_step() {
+            let dominated = self.3.get(&self.1(&step))
+                .map(|frontier| frontier.iter().any(|f| self.2(f, &step) && !self.2(&step, f)))
+                .unwrap_or(false);
+
+            if !dominated {
+                return Some(step);
+            }
+
+            self.4 += 1;
+        }
+
+        None
     }
 
-    fn add_step(&mut self, step: S) {
-        if self.seen.insert(step.clone()) {
-            self.stack.push(step);
+    fn try_add_step(&mut self, step: S) -> bool {
+        let frontier = self.3.entry(self.1(&step)).or_default();
+        if frontier.iter().any(|f| self.2(f, &step)) {
+            self.4 += 1;
+            return false;
+        }
+
+        if !self.0.try_add_step(step.clone()) {
+            return false;
         }
+
+        frontier.retain(|f| !self.2(&step, f));
+        frontier.push(step);
+        true
+    }
+
+    fn stats(&self) -> SearchStats {
+        SearchStats { duplicates: self.0.stats().duplicates + self.4, ..self.0.stats() }
     }
 }
 
-pub fn dfs<S>(initial_step: S) -> impl Search<S> where S: Default + Hash + Eq + Clone {
-    let mut dfs = DFS { stack: Vec::with_capacity(64), seen: FxHashSet::default() };
-    dfs.add_step(initial_step);
-    dfs
+/// Check if every value in `a` is at least as large as the one in `b`, which is the usual
+/// dominance check for `Search::pareto`.
+#[inline]
+pub fn dominates<T: PartialOrd, const N: usize>(a: &[T; N], b: &[T; N]) -> bool {
+    a.iter().zip(b.iter()).all(|(a, b)| a >= b)
 }
 
-struct BFS<S> {
-    queue: VecDeque<S>,
-    seen: FxHashSet<S>,
+pub struct WithPaths<SEARCH, S, C, F> {
+    search: SEARCH,
+    cost: F,
+    all: bool,
+    current: Option<S>,
+    parents: FxHashMap<S, (C, Vec<S>)>,
 }
 
-impl<S> Search<S> for BFS<S> where S: Hash + Eq + Clone {
-    fn reset(&mut self) {
-        self.queue.clear();
-        self.seen.clear();
+impl<SEARCH, S, C, F> WithPaths<SEARCH, S, C, F> where S: Hash + Eq + Clone, C: PartialOrd, F: Fn(&S) -> C {
+    fn new(search: SEARCH, cost: F, all: bool) -> Self {
+        Self { search, cost, all, current: None, parents: FxHashMap::default() }
     }
 
-    fn next_step(&mut self) -> Option<S> {
-        self.queue.pop_front()
+    /// Get the path from the initial state to the state, both included. It returns `None` if
+    /// the state hasn't been discovered.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        let (key, _) = self.parents.get_key_value(state)?;
+        let mut path = vec![key.clone()];
+        while let Some((_, parents)) = self.parents.get(path.last().unwrap()) {
+            // A search without a seen set can loop back on itself.
+            match parents.first() {
+                Some(parent) if path.len() <= self.parents.len() => path.push(parent.clone()),
+                _ => break,
+            }
+        }
+
+        path.reverse();
+        Some(path)
     }
 
-    fn add_step(&mut self, step: S) {
-        if self.seen.insert(step.clone()) {
-            self.queue.push_back(step);
+    /// Get all the paths from the initial state to the state. Without `with_all_paths`, there's
+    /// only the one.
+    pub fn paths_to(&self, state: &S) -> Vec<Vec<S>> {
+        let mut res = Vec::new();
+        if let Some((key, _)) = self.parents.get_key_value(state) {
+            let mut path = vec![key.clone()];
+            self.collect_paths(&mut path, &mut res);
         }
-    }
-}
 
-pub fn bfs<S>(initial_step: S) -> impl Search<S> where S: Hash + Eq + Clone {
-    let mut bfs = BFS { queue: VecDeque::with_capacity(64), seen: FxHashSet::default() };
-    bfs.add_step(initial_step);
-    bfs
-}
+        res
+    }
 
-struct BFSUnhashed<S> {
-    queue: VecDeque<S>,
+    fn collect_paths(&self, path: &mut Vec<S>, res: &mut Vec<Vec<S>>) {
+        match self.parents.get(path.last().unwrap()) {
+            Some((_, parents)) if !parents.is_empty() && path.len() <= self.parents.len() => {
+                for parent in parents.iter() {
+                    path.push(parent.clone());
+                    self.collect_paths(path, res);
+                    path.pop();
+                }
+            }
+            _ => {
+                res.push(path.iter().rev().cloned().collect());
+            }
+        }
+    }
 }
 
-impl<S> Search<S> for BFSUnhashed<S> where S: Clone {
+impl<SEARCH, S, C, F> Search<S> for WithPaths<SEARCH, S, C, F>
+    where SEARCH: Search<S>,
+          S: Hash + Eq + Clone,
+          C: PartialOrd,
+          F: Fn(&S) -> C {
     fn reset(&mut self) {
-        self.queue.clear();
+        self.search.reset();
+        self.current = None;
+        self.parents.clear();
     }
 
     fn next_step(&mut self) -> Option<S> {
-        self.queue.pop_front()
+        let step = self.search.next_step()?;
+        if !self.parents.contains_key(&step) {
+            self.parents.insert(step.clone(), ((self.cost)(&step), Vec::new()));
+        }
+
+        self.current = Some(step.clone());
+        Some(step)
     }
 
-    fn add_step(&mut self, step: S) {
-        self.queue.push_back(step);
+    fn try_add_step(&mut self, step: S) -> bool {
+        let accepted = self.search.try_add_step(step.clone());
+        let parent: Vec<S> = self.current.iter().cloned().collect();
+
+        if !self.all {
+            if accepted {
+                let cost = (self.cost)(&step);
+                self.parents.insert(step, (cost, parent));
+            }
+        } else {
+            let cost = (self.cost)(&step);
+            match self.parents.entry(step) {
+                Entry::Occupied(mut entry) => {
+                    let (seen_cost, parents) = entry.get_mut();
+                    if cost < *seen_cost {
+                        *seen_cost = cost;
+                        *parents = parent;
+                    } else if cost == *seen_cost && !parent.iter().all(|p| parents.contains(p)) {
+                        parents.extend(parent);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((cost, parent));
+                }
+            }
+        }
+
+        accepted
     }
-}
 
-pub fn bfs_unhashed<S>(initial_step: S) -> impl Search<S> where S: Clone {
-    let mut bfs = BFSUnhashed { queue: VecDeque::with_capacity(64) };
-    bfs.add_step(initial_step);
-    bfs
+    #[inline]
+    fn stats(&self) -> SearchStats {
+        self.search.stats()
+    }
 }
 
-struct Dijkstra<C, K, S> where C: Ord + Eq, K: Hash + Eq, S: DijkstraState<C, K> {
-    initial_step: S,
-    seen: FxHashMap<K, C>,
-    open: BinaryHeap<DijkstraStep<C, K, S>>,
+pub struct Goals<SEARCH, S, C, FG, FC> {
+    search: SEARCH,
+    is_goal: FG,
+    cost: FC,
+    optimal: bool,
+    prune: bool,
+    best: Option<C>,
+    goals: Vec<S>,
+    skipped: usize,
 }
 
-impl<C, H, S> Search<S> for Dijkstra<C, H, S> where C: Ord + Eq, H: Hash + Eq + PartialEq, S: DijkstraState<C, H> {
-    fn reset(&mut self) {
-        self.open.clear();
-        self.seen.clear();
+impl<SEARCH, S, C, FG, FC> Goals<SEARCH, S, C, FG, FC> where C: PartialOrd, FC: Fn(&S) -> C {
+    fn new(search: SEARCH, is_goal: FG, cost: FC, optimal: bool) -> Self {
+        Self { search, is_goal, cost, optimal, prune: false, best: None, goals: Vec::with_capacity(8), skipped: 0 }
+    }
 
-        self.open.push(DijkstraStep(
-            self.initial_step.cost(),
-            self.initial_step.clone(),
-            PhantomData::default())
-        );
-        self.seen.insert(self.initial_step.key(), self.initial_step.cost());
+    /// Also reject the states that cost more than the best goal when they're added, and not only
+    /// when they come up. They're counted as bounded.
+    pub fn prune_by_best(mut self) -> Self {
+        self.prune = true;
+        self
     }
 
-    fn next_step(&mut self) -> Option<S> {
-        self.open.pop().map(|DijkstraStep(_, s, _)| s)
+    /// Get the goals in the order they were found.
+    pub fn goals(&self) -> &[S] {
+        &self.goals
     }
 
-    fn add_step(&mut self, step: S) {
-        let seen_key = step.key();
-        let step_cost = step.cost();
+    /// Get the cost of the best goal from `optimal_goals`.
+    pub fn best_cost(&self) -> Option<&C> {
+        self.best.as_ref()
+    }
+
+    #[inline]
+    fn costs_more(&self, step: &S) -> bool {
+        matches!(&self.best, Some(best) if (self.cost)(step) > *best)
+    }
+}
+
+impl<SEARCH, S, C, FG, FC> Search<S> for Goals<SEARCH, S, C, FG, FC>
+    where SEARCH: Search<S>,
+          S: Clone,
+          C: PartialOrd,
+          FG: Fn(&S) -> bool,
+          FC: Fn(&S) -> C {
+    fn reset(&mut self) {
+        self.search.reset();
+        self.best = None;
+        self.goals.clear();
+        self.skipped = 0;
+    }
 
-        match self.seen.entry(seen_key) {
-            Entry::Occupied(mut entry) => {
-                let seen_cost = entry.get_mut();
-                if step_cost >= *seen_cost {
-                    return;
+    fn next_step(&mut self) -> Option<S> {
+        while let Some(step) = self.search.next_step() {
+            if !self.optimal {
+                if (self.is_goal)(&step) {
+                    self.goals.push(step.clone());
                 }
-                *seen_cost = step_cost;
+
+                return Some(step);
+            }
+
+            if self.costs_more(&step) {
+                self.skipped += 1;
+                continue;
+            }
+            if !(self.is_goal)(&step) {
+                return Some(step);
             }
-            Entry::Vacant(entry) => {
-                entry.insert(step_cost);
+
+            let cost = (self.cost)(&step);
+            if !matches!(&self.best, Some(best) if cost >= *best) {
+                self.goals.clear();
+                self.best = Some(cost);
             }
+            self.goals.push(step);
         }
 
-        self.open.push(DijkstraStep(
-            step.cost(), step,
-            PhantomData::default())
-        );
+        None
     }
-}
 
-struct DijkstraStep<C, K, S> (C, S, PhantomData<K>);
+    fn try_add_step(&mut self, step: S) -> bool {
+        if self.prune && self.costs_more(&step) {
+            self.skipped += 1;
+            return false;
+        }
 
-impl<C, K, S> Eq for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {}
+        self.search.try_add_step(step)
+    }
 
-impl<C, K, S> PartialEq<Self> for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+    fn stats(&self) -> SearchStats {
+        SearchStats { bounded: self.search.stats().bounded + self.skipped, ..self.search.stats() }
     }
 }
 
-impl<C, K, S> PartialOrd<Self> for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.0.partial_cmp(&self.0)
-    }
+/// SeenSet is the storage of the states that dfs and bfs have seen.
+pub trait SeenSet<S> {
+    /// Insert the state, and return whether it's new. States that can't be stored are never new.
+    fn insert(&mut self, state: &S) -> bool;
+    fn clear(&mut self);
 }
 
-impl<C, K, S> Ord for DijkstraStep<C, K, S> where S: DijkstraState<C, K>, C: Ord + Eq, K: Hash + Eq {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.0.cmp(&self.0)
+impl<S> SeenSet<S> for FxHashSet<S> where S: Hash + Eq + Clone {
+    #[inline]
+    fn insert(&mut self, state: &S) -> bool {
+        FxHashSet::insert(self, state.clone())
     }
-}
 
-pub trait DijkstraState<C: Ord + Eq, K: Hash + Eq>: Clone {
-    fn cost(&self) -> C;
-    fn key(&self) -> K;
+    #[inline]
+    fn clear(&mut self) {
+        FxHashSet::clear(self)
+    }
 }
 
-/// WithCost is a wrapper type for a state that can be used for bfs, dfs and dijkstra that will
-/// allow you to associate cost without it making dfs/bfs run forever.
-#[derive(Clone, Copy, Default)]
-pub struct WithCost<S, C> (pub S, pub C);
+/// CostMap is the storage of the lowest cost of each key that dijkstra has seen.
+pub trait CostMap<K, C> {
+    fn get(&self, key: &K) -> Option<&C>;
+    /// Set the cost of the key, and return whether it could be stored.
+    fn insert(&mut self, key: K, cost: C) -> bool;
+    fn clear(&mut self);
+}
 
-impl<S, C> PartialEq<Self> for WithCost<S, C> where S: PartialEq {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+impl<K, C> CostMap<K, C> for FxHashMap<K, C> where K: Hash + Eq {
+    #[inline]
+    fn get(&self, key: &K) -> Option<&C> {
+        FxHashMap::get(self, key)
     }
-}
 
-impl<S, C> Eq for WithCost<S, C> where S: PartialEq {}
+    #[inline]
+    fn insert(&mut self, key: K, cost: C) -> bool {
+        FxHashMap::insert(self, key, cost);
+        true
+    }
 
-impl<S, C> Hash for WithCost<S, C> where S: Hash + Eq {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
+    #[inline]
+    fn clear(&mut self) {
+        FxHashMap::clear(self)
     }
 }
 
-impl<S, C> DijkstraState<C, S> for WithCost<S, C> where S: Hash + Eq + Clone, C: Ord + Eq + Copy + Clone {
-    fn cost(&self) -> C {
-        self.1
-    }
+/// IndexSize is the number of indices of a `StateIndex`.
+pub trait IndexSize {
+    fn size(&self) -> usize;
+}
 
-    fn key(&self) -> S {
-        self.0.clone()
-    }
+/// StateIndex maps states to the indices `0..size()` without any collisions, so that they can
+/// be stored in a `DenseSet` or a `DenseMap`.
+pub trait StateIndex<S>: IndexSize {
+    /// Get the index of the state, or `None` if it's outside of the range.
+    fn index(&self, state: &S) -> Option<usize>;
 }
 
-/// WithCost is a wrapper type for a state that can be used for bfs, dfs and dijkstra that will
-/// allow you to associate cost without it making dfs/bfs run forever.
-#[derive(Clone, Copy, Default)]
-pub struct WithCostHeuristic<S, C> (pub S, pub C, pub C);
+/// GridIndex indexes the points in a grid in reading order.
+#[derive(Clone, Copy, Debug)]
+pub struct GridIndex {
+    pub width: usize,
+    pub height: usize,
+}
 
-impl<S, C> PartialEq<Self> for WithCostHeuristic<S, C> where S: PartialEq {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+impl IndexSize for GridIndex {
+    #[inline]
+    fn size(&self) -> usize {
+        self.width * self.height
     }
 }
 
-impl<S, C> Eq for WithCostHeuristic<S, C> where S: PartialEq {}
-
-impl<S, C> Hash for WithCostHeuristic<S, C> where S: Hash + Eq {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
+impl StateIndex<Point<usize>> for GridIndex {
+    #[inline]
+    fn index(&self, state: &Point<usize>) -> Option<usize> {
+        if state.0 < self.width && state.1 < self.height {
+            Some(state.1 * self.width + state.0)
+        } else {
+            None
+        }
     }
 }
 
-impl<S, C> DijkstraState<C, S> for WithCostHeuristic<S, C> where S: Hash + Eq + Clone, C: Ord + Eq + Copy + Clone + Add<Output=C> {
-    fn cost(&self) -> C {
-        self.1 + self.2
+/// VoxelIndex indexes the vertices in a box in the same order as the voxel grids.
+#[derive(Clone, Copy, Debug)]
+pub struct VoxelIndex {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+}
+
+impl IndexSize for VoxelIndex {
+    #[inline]
+    fn size(&self) -> usize {
+        self.width * self.height * self.depth
     }
+}
 
-    fn key(&self) -> S {
-        self.0.clone()
+impl StateIndex<Vertex<usize>> for VoxelIndex {
+    #[inline]
+    fn index(&self, state: &Vertex<usize>) -> Option<usize> {
+        if state.0 < self.width && state.1 < self.height && state.2 < self.depth {
+            Some((state.2 * self.height + state.1) * self.width + state.0)
+        } else {
+            None
+        }
     }
 }
 
-/// Run dijkstra search. The state needs to implement DijkstraState<T> and is not interchangeable
-/// with bfs and dfs without changing up how costs and such are handled.
-pub fn dijkstra<C, K, S>(initial_step: S) -> impl Search<S> where C: Ord + Eq, K: Hash + Eq, S: DijkstraState<C, K> {
-    let mut dijkstra = Dijkstra {
-        initial_step,
-        seen: FxHashMap::default(),
-        open: BinaryHeap::with_capacity(128),
+macro_rules! index_with_cost {
+    ($($index:ty => $s:ty),*) => {
+        $(
+            impl<C> StateIndex<WithCost<$s, C>> for $index {
+                #[inline]
+                fn index(&self, state: &WithCost<$s, C>) -> Option<usize> { self.index(&state.0) }
+            }
+
+            impl<C> StateIndex<WithCostHeuristic<$s, C>> for $index {
+                #[inline]
+                fn index(&self, state: &WithCostHeuristic<$s, C>) -> Option<usize> { self.index(&state.0) }
+            }
+        )*
     };
+}
 
-    dijkstra.reset();
+index_with_cost!(GridIndex => Point<usize>, VoxelIndex => Vertex<usize>);
 
-    dijkstra
+/// IndexFn is a `StateIndex` from a length and a function, for perfect hashes of other states.
+#[derive(Clone, Copy)]
+pub struct IndexFn<F> (pub usize, pub F);
+
+impl<F> IndexSize for IndexFn<F> {
+    #[inline]
+    fn size(&self) -> usize {
+        self.0
+    }
 }
 
+impl<S, F> StateIndex<S> for IndexFn<F> where F: Fn(&S) -> Option<usize> {
+    #[inline]
+    fn index(&self, state: &S) -> Option<usize> {
+        self.1(state)
+    }
+}
 
-#[cfg(test)]
-pub mod tests {
+/// DenseSet is a bitset of the states in a `StateIndex`.
+pub struct DenseSet<I> {
+    index: I,
+    bits: Vec<u64>,
+}
+
+impl<I> DenseSet<I> {
+    pub fn new(index: I) -> Self where I: IndexSize {
+        let bits = vec![0; index.size().div_ceil(64)];
+        Self { index, bits }
+    }
+
+    pub fn contains<S>(&self, state: &S) -> bool where I: StateIndex<S> {
+        self.index.index(state)
+            .map(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+            .unwrap_or(false)
+    }
+}
+
+impl<S, I> SeenSet<S> for DenseSet<I> where I: StateIndex<S> {
+    #[inline]
+    fn insert(&mut self, state: &S) -> bool {
+        match self.index.index(state) {
+            Some(i) => {
+                let mask = 1 << (i % 64);
+                let new = self.bits[i / 64] & mask == 0;
+                self.bits[i / 64] |= mask;
+                new
+            }
+            None => false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+/// DenseMap is an array of the costs of the keys in a `StateIndex`.
+pub struct DenseMap<I, C> {
+    index: I,
+    costs: Vec<Option<C>>,
+}
+
+impl<I, C> DenseMap<I, C> where C: Clone {
+    pub fn new(index: I) -> Self where I: IndexSize {
+        let costs = vec![None; index.size()];
+        Self { index, costs }
+    }
+}
+
+impl<K, C, I> CostMap<K, C> for DenseMap<I, C> where I: StateIndex<K> {
+    #[inline]
+    fn get(&self, key: &K) -> Option<&C> {
+        self.costs[self.index.index(key)?].as_ref()
+    }
+
+    #[inline]
+    fn insert(&mut self, key: K, cost: C) -> bool {
+        match self.index.index(&key) {
+            Some(i) => {
+                self.costs[i] = Some(cost);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.costs.iter_mut().for_each(|c| *c = None);
+    }
+}
+
+struct DFS<S, SEEN> {
+    stack: Vec<S>,
+    seen: SEEN,
+    stats: SearchStats,
+}
+
+impl<S, SEEN> Search<S> for DFS<S, SEEN> where SEEN: SeenSet<S> {
+    fn reset(&mut self) {
+        self.stack.clear();
+        self.seen.clear();
+        self.stats = SearchStats::default();
+    }
+
+    fn next_step(&mut self) -> Option<S> {
+        self.stats.pop(self.stack.pop())
+    }
+
+    fn try_add_step(&mut self, step: S) -> bool {
+        if self.seen.insert(&step) {
+            self.stack.push(step);
+            self.stats.push(self.stack.len());
+            true
+        } else {
+            self.stats.duplicates += 1;
+            false
+        }
+    }
+
+    fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+pub fn dfs<S>(initial_step: S) -> impl Search<S> where S: Default + Hash + Eq + Clone {
+    dfs_with(initial_step, FxHashSet::default())
+}
+
+/// Run a dfs that keeps track of the seen states in `seen`, like a `DenseSet`.
+pub fn dfs_with<S, SEEN>(initial_step: S, seen: SEEN) -> impl Search<S> where SEEN: SeenSet<S> {
+    let mut dfs = DFS { stack: Vec::with_capacity(64), seen, stats: SearchStats::default() };
+    dfs.add_step(initial_step);
+    dfs
+}
+
+struct BFS<S, SEEN> {
+    queue: VecDeque<S>,
+    seen: SEEN,
+    stats: SearchStats,
+}
+
+impl<S, SEEN> Search<S> for BFS<S, SEEN> where SEEN: SeenSet<S> {
+    fn reset(&mut self) {
+        self.queue.clear();
+        self.seen.clear();
+        self.stats = SearchStats::default();
+    }
+
+    fn next_step(&mut self) -> Option<S> {
+        self.stats.pop(self.queue.pop_front())
+    }
+
+    fn try_add_step(&mut self, step: S) -> bool {
+        if self.seen.insert(&step) {
+            self.queue.push_back(step);
+            self.stats.push(self.queue.len());
+            true
+        } else {
+            self.stats.duplicates += 1;
+            false
+        }
+    }
+
+    fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+pub fn bfs<S>(initial_step: S) -> impl Search<S> where S: Hash + Eq + Clone {
+    bfs_with(initial_step, FxHashSet::default())
+}
+
+/// Run a bfs that keeps track of the seen states in `seen`, like a `DenseSet`.
+pub fn bfs_with<S, SEEN>(initial_step: S, seen: SEEN) -> impl Search<S> where SEEN: SeenSet<S> {
+    let mut bfs = BFS { queue: VecDeque::with_capacity(64), seen, stats: SearchStats::default() };
+    bfs.add_step(initial_step);
+    bfs
+}
+
+struct BFSUnhashed<S> {
+    queue: VecDeque<S>,
+    stats: SearchStats,
+}
+
+impl<S> Search<S> for BFSUnhashed<S> where S: Clone {
+    fn reset(&mut self) {
+        self.queue.clear();
+        self.stats = SearchStats::default();
+    }
+
+    fn next_step(&mut self) -> Option<S> {
+        self.stats.pop(self.queue.pop_front())
+    }
+
+    fn try_add_step(&mut self, step: S) -> bool {
+        self.queue.push_back(step);
+        self.stats.push(self.queue.len());
+        true
+    }
+
+    fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+pub fn bfs_unhashed<S>(initial_step: S) -> impl Search<S> where S: Clone {
+    let mut bfs = BFSUnhashed { queue: VecDeque::with_capacity(64), stats: SearchStats::default() };
+    bfs.add_step(initial_step);
+    bfs
+}
+
+struct Dijkstra<C, K, S, SEEN> where C: Ord + Eq, K: Hash + Eq, S: DijkstraState<C, K> {
+    initial_step: S,
+    seen: SEEN,
+    open: BinaryHeap<DijkstraStep<C, K, S>>,
+    stats: SearchStats,
+}
+
+impl<C, H, S, SEEN> Search<S> for Dijkstra<C, H, S, SEEN> where C: Ord + Eq, H: Hash + Eq + PartialEq, S: DijkstraState<C, H>, SEEN: CostMap<H, C> {
+    fn reset(&mut self) {
+        self.open.clear();
+        self.seen.clear();
+        self.stats = SearchStats::default();
+
+        self.open.push(DijkstraStep(
+            self.initial_step.cost(),
+            self.initial_step.clone(),
+            PhantomData::default())
+        );
+        self.seen.insert(self.initial_step.key(), self.initial_step.cost());
+        self.stats.push(1);
+    }
+
+    fn next_step(&mut self) -> Option<S> {
+        self.stats.pop(self.open.pop().map(|DijkstraStep(_, s, _)| s))
+    }
+
+    fn try_add_step(&mut self, step: S) -> bool {
+        let step_cost = step.cost();
+        if let Some(seen_cost) = self.seen.get(&step.key()) {
+            if step_cost >= *seen_cost {
+                self.stats.duplicates += 1;
+                return false;
+            }
+        }
+        if !self.seen.insert(step.key(), step_cost) {
+            self.stats.duplicates += 1;
+            return false;
+        }
+
+        self.open.push(DijkstraStep(
+            step.cost(), step,
+            PhantomData::default())
+        );
+        self.stats.push(self.open.len());
+
+        true
+    }
+
+    fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+struct DijkstraStep<C, K, S> (C, S, PhantomData<K>);
+
+impl<C, K, S> Eq for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {}
+
+impl<C, K, S> PartialEq<Self> for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<C, K, S> PartialOrd<Self> for DijkstraStep<C, K, S> where C: Eq + Ord, K: Eq + Hash, S: DijkstraState<C, K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        other.0.partial_cmp(&self.0)
+    }
+}
+
+impl<C, K, S> Ord for DijkstraStep<C, K, S> where S: DijkstraState<C, K>, C: Ord + Eq, K: Hash + Eq {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+pub trait DijkstraState<C: Ord + Eq, K: Hash + Eq>: Clone {
+    fn cost(&self) -> C;
+    fn key(&self) -> K;
+}
+
+/// WithCost is a wrapper type for a state that can be used for bfs, dfs and dijkstra that will
+/// allow you to associate cost without it making dfs/bfs run forever.
+#[derive(Clone, Copy, Default)]
+pub struct WithCost<S, C> (pub S, pub C);
+
+impl<S, C> PartialEq<Self> for WithCost<S, C> where S: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<S, C> Eq for WithCost<S, C> where S: PartialEq {}
+
+impl<S, C> Hash for WithCost<S, C> where S: Hash + Eq {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<S, C> DijkstraState<C, S> for WithCost<S, C> where S: Hash + Eq + Clone, C: Ord + Eq + Copy + Clone {
+    fn cost(&self) -> C {
+        self.1
+    }
+
+    fn key(&self) -> S {
+        self.0.clone()
+    }
+}
+
+/// WithCost is a wrapper type for a state that can be used for bfs, dfs and dijkstra that will
+/// allow you to associate cost without it making dfs/bfs run forever.
+#[derive(Clone, Copy, Default)]
+pub struct WithCostHeuristic<S, C> (pub S, pub C, pub C);
+
+impl<S, C> PartialEq<Self> for WithCostHeuristic<S, C> where S: PartialEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<S, C> Eq for WithCostHeuristic<S, C> where S: PartialEq {}
+
+impl<S, C> Hash for WithCostHeuristic<S, C> where S: Hash + Eq {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<S, C> DijkstraState<C, S> for WithCostHeuristic<S, C> where S: Hash + Eq + Clone, C: Ord + Eq + Copy + Clone + Add<Output=C> {
+    fn cost(&self) -> C {
+        self.1 + self.2
+    }
+
+    fn key(&self) -> S {
+        self.0.clone()
+    }
+}
+
+/// Run dijkstra search. The state needs to implement DijkstraState<T> and is not interchangeable
+/// with bfs and dfs without changing up how costs and such are handled.
+pub fn dijkstra<C, K, S>(initial_step: S) -> impl Search<S> where C: Ord + Eq, K: Hash + Eq, S: DijkstraState<C, K> {
+    dijkstra_with(initial_step, FxHashMap::default())
+}
+
+/// Run dijkstra search that keeps the lowest cost of each key in `seen`, like a `DenseMap`.
+pub fn dijkstra_with<C, K, S, SEEN>(initial_step: S, seen: SEEN) -> impl Search<S> where C: Ord + Eq, K: Hash + Eq, S: DijkstraState<C, K>, SEEN: CostMap<K, C> {
+    let mut dijkstra = Dijkstra {
+        initial_step,
+        seen,
+        open: BinaryHeap::with_capacity(128),
+        stats: SearchStats::default(),
+    };
+
+    dijkstra.reset();
+
+    dijkstra
+}
+
+
+/// BranchAndBound is a depth first search for the best score, which skips the states that can't
+/// do better than the best one found so far. Create it with `maximize` or `minimize`.
+pub struct BranchAndBound<S, V, K, FK> {
+    initial_step: S,
+    maximize: bool,
+    memo_key: Option<FK>,
+    memo: FxHashMap<K, V>,
+    incumbent: Option<(V, S)>,
+    best: Option<(V, S)>,
+    expanded: usize,
+    stats: SearchStats,
+}
+
+impl<S, V, K, FK> BranchAndBound<S, V, K, FK> where S: Clone, V: Ord + Copy, K: Hash + Eq, FK: Fn(&S) -> (K, V) {
+    /// Skip the states where another state with the same key has been expanded with an equal or
+    /// better value. The key must hold everything that decides what can be reached from the
+    /// state, and the value is usually the score so far.
+    pub fn memo<K2, FK2>(self, key: FK2) -> BranchAndBound<S, V, K2, FK2> where K2: Hash + Eq, FK2: Fn(&S) -> (K2, V) {
+        BranchAndBound {
+            initial_step: self.initial_step,
+            maximize: self.maximize,
+            memo_key: Some(key),
+            memo: FxHashMap::default(),
+            incumbent: self.incumbent,
+            best: None,
+            expanded: 0,
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Start with a solution that's already known, like one from `beam_search`, so that the
+    /// states that can't beat it are pruned from the start. It's returned if nothing beats it.
+    pub fn incumbent(mut self, score: V, state: S) -> Self {
+        self.incumbent = Some((score, state));
+        self
+    }
+
+    /// Run the search. The `successors` callback adds the next states to the list, `score`
+    /// returns the score of a state if it counts as a solution, and `bound` returns the best
+    /// score any solution reachable from the state could have, including the state itself. It
+    /// must never be worse than the real one, or the best solution may be pruned.
+    pub fn run<FS, FSC, FB>(&mut self, mut successors: FS, score: FSC, bound: FB) -> Option<V>
+        where FS: FnMut(&S, &mut Vec<S>),
+              FSC: Fn(&S) -> Option<V>,
+              FB: Fn(&S) -> V {
+        self.memo.clear();
+        self.best = self.incumbent.clone();
+        self.expanded = 0;
+        self.stats = SearchStats::default();
+
+        let mut stack = Vec::with_capacity(64);
+        let mut next = Vec::with_capacity(16);
+        stack.push(self.initial_step.clone());
+        self.stats.push(stack.len());
+
+        while let Some(state) = self.stats.pop(stack.pop()) {
+            if let Some(score) = score(&state) {
+                if self.best.as_ref().map(|(best, _)| self.better(score, *best)).unwrap_or(true) {
+                    self.best = Some((score, state.clone()));
+                }
+            }
+
+            if let Some((best, _)) = self.best.as_ref() {
+                if !self.better(bound(&state), *best) {
+                    self.stats.bounded += 1;
+                    continue;
+                }
+            }
+
+            if let Some(memo_key) = self.memo_key.as_ref() {
+                let (key, value) = memo_key(&state);
+                match self.memo.entry(key) {
+                    Entry::Occupied(mut entry) => {
+                        if !(if self.maximize { value > *entry.get() } else { value < *entry.get() }) {
+                            self.stats.duplicates += 1;
+                            continue;
+                        }
+                        entry.insert(value);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(value);
+                    }
+                }
+            }
+
+            self.expanded += 1;
+            successors(&state, &mut next);
+            self.stats.push_many(next.len(), stack.len() + next.len());
+            stack.append(&mut next);
+        }
+
+        self.best.as_ref().map(|(best, _)| *best)
+    }
+
+    /// Get the best score and the state it was found in, after running.
+    pub fn best(&self) -> Option<(V, &S)> {
+        self.best.as_ref().map(|(best, state)| (*best, state))
+    }
+
+    /// Get the number of states whose successors were generated.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Get the number of states skipped because of the bound or the memo.
+    pub fn pruned(&self) -> usize {
+        self.stats.bounded + self.stats.duplicates
+    }
+
+    /// Get the stats of the last run, where the states skipped because of the bound are counted
+    /// as `bounded` and the ones skipped because of the memo as `duplicates`.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    #[inline]
+    fn better(&self, a: V, b: V) -> bool {
+        if self.maximize { a > b } else { a < b }
+    }
+}
+
+/// NoMemo is the memo key function type of a branch and bound search without a memo.
+pub type NoMemo<S, V> = fn(&S) -> ((), V);
+
+/// Search for the highest score with branch and bound.
+pub fn maximize<S, V>(initial_step: S) -> BranchAndBound<S, V, (), NoMemo<S, V>> where S: Clone, V: Ord + Copy {
+    BranchAndBound {
+        initial_step,
+        maximize: true,
+        memo_key: None,
+        memo: FxHashMap::default(),
+        incumbent: None,
+        best: None,
+        expanded: 0,
+        stats: SearchStats::default(),
+    }
+}
+
+/// Search for the lowest score with branch and bound. The bound is then a lower bound.
+pub fn minimize<S, V>(initial_step: S) -> BranchAndBound<S, V, (), NoMemo<S, V>> where S: Clone, V: Ord + Copy {
+    BranchAndBound {
+        maximize: false,
+        ..maximize(initial_step)
+    }
+}
+
+/// BeamSearch goes through the states one depth at a time, and only keeps the `width` states with
+/// the highest score of each depth. It can miss the best solution, but it's quick to find a good
+/// one, which can also be the incumbent of a `BranchAndBound`. Create it with `beam_search`.
+pub struct BeamSearch<S, V, K, FK> {
+    initial_step: S,
+    width: usize,
+    max_depth: Option<usize>,
+    dedupe_key: Option<FK>,
+    beams: Vec<Vec<(V, S)>>,
+    best: Option<(V, S)>,
+    key: PhantomData<K>,
+}
+
+impl<S, V, K, FK> BeamSearch<S, V, K, FK> where S: Clone, V: Ord + Copy, K: Hash + Eq, FK: Fn(&S) -> K {
+    /// Keep only the best state of each key in a beam.
+    pub fn dedupe<K2, FK2>(self, key: FK2) -> BeamSearch<S, V, K2, FK2> where K2: Hash + Eq, FK2: Fn(&S) -> K2 {
+        BeamSearch {
+            initial_step: self.initial_step,
+            width: self.width,
+            max_depth: self.max_depth,
+            dedupe_key: Some(key),
+            beams: Vec::new(),
+            best: None,
+            key: PhantomData,
+        }
+    }
+
+    /// Stop after `depth` moves from the initial state, and count the states of that beam as
+    /// final ones. It's needed when the states can go on forever, like when they can wait.
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Run the search. The `successors` callback adds the next states to the list, and `score`
+    /// ranks them with the highest first. States with the same score are kept in the order they
+    /// were added. The states without any successors are the final ones, and it returns the
+    /// best score among them. Without a `depth`, `successors` must run out of states at some
+    /// point, or the search never ends.
+    pub fn run<FS, FSC>(&mut self, mut successors: FS, score: FSC) -> Option<V>
+        where FS: FnMut(&S, &mut Vec<S>),
+              FSC: Fn(&S) -> V {
+        self.beams.clear();
+        self.best = None;
+
+        let mut beam = vec![(score(&self.initial_step), self.initial_step.clone())];
+        let mut candidates = Vec::with_capacity(self.width * 4);
+        let mut next = Vec::with_capacity(16);
+        let mut keys = FxHashSet::default();
+
+        while !beam.is_empty() {
+            if self.max_depth.map(|depth| self.beams.len() >= depth).unwrap_or(false) {
+                for (value, state) in beam.iter() {
+                    if self.best.as_ref().map(|(best, _)| value > best).unwrap_or(true) {
+                        self.best = Some((*value, state.clone()));
+                    }
+                }
+
+                self.beams.push(beam);
+                break;
+            }
+
+            for (value, state) in beam.iter() {
+                successors(state, &mut next);
+                if next.is_empty() && self.best.as_ref().map(|(best, _)| value > best).unwrap_or(true) {
+                    self.best = Some((*value, state.clone()));
+                }
+
+                candidates.extend(next.drain(..).map(|s| (score(&s), s)));
+            }
+
+            candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+            if let Some(key) = self.dedupe_key.as_ref() {
+                keys.clear();
+                candidates.retain(|(_, s)| keys.insert(key(s)));
+            }
+            candidates.truncate(self.width);
+
+            let next_beam = std::mem::replace(&mut candidates, Vec::with_capacity(self.width * 4));
+            self.beams.push(std::mem::replace(&mut beam, next_beam));
+        }
+
+        self.best.as_ref().map(|(best, _)| *best)
+    }
+
+    /// Get the best final score and state, after running.
+    pub fn best(&self) -> Option<(V, &S)> {
+        self.best.as_ref().map(|(best, state)| (*best, state))
+    }
+
+    /// Get the beam of each depth with the scores, starting with the initial state.
+    pub fn beams(&self) -> &[Vec<(V, S)>] {
+        &self.beams
+    }
+}
+
+/// NoDedupe is the key function type of a beam search that keeps duplicates.
+pub type NoDedupe<S> = fn(&S);
+
+/// Search for a high score by keeping the best `width` states of each depth.
+pub fn beam_search<S, V>(initial_step: S, width: usize) -> BeamSearch<S, V, (), NoDedupe<S>> where S: Clone, V: Ord + Copy {
+    BeamSearch {
+        initial_step,
+        width,
+        max_depth: None,
+        dedupe_key: None,
+        beams: Vec::new(),
+        best: None,
+        key: PhantomData,
+    }
+}
+
+/// IterationStats are the numbers for one iteration of an `IterativeDeepening` search.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IterationStats<C> {
+    /// The depth or cost limit of the iteration.
+    pub limit: C,
+    /// The number of states whose successors were generated.
+    pub expanded: usize,
+    /// The number of states over the limit or found in the transposition table.
+    pub pruned: usize,
+}
+
+/// IterativeDeepening runs depth first searches with a growing depth or cost limit, which finds
+/// the shortest path while only keeping the current path and the unvisited successors along it
+/// in memory. States already on the path are skipped. Create it with `iddfs` or `ida_star`.
+pub struct IterativeDeepening<S, C, K, FK> {
+    initial_step: S,
+    table_key: Option<FK>,
+    table: FxHashMap<K, C>,
+    iterations: Vec<IterationStats<C>>,
+    stats: SearchStats,
+}
+
+/// The result of one iteration, which is either the cost of the goal or the lowest cost over
+/// the limit to try next.
+enum Iteration<C> {
+    Found(C),
+    Exceeded(Option<C>),
+}
+
+impl<S, C, K, FK> IterativeDeepening<S, C, K, FK>
+    where S: Eq + Clone,
+          C: Ord + Copy + Add<Output=C> + Zero,
+          K: Hash + Eq,
+          FK: Fn(&S) -> K {
+    /// Keep a transposition table of the lowest cost each key has been reached with during an
+    /// iteration, and skip the states that are reached again without doing better. It trades
+    /// the small memory use for fewer states when paths often cross.
+    pub fn transpositions<K2, FK2>(self, key: FK2) -> IterativeDeepening<S, C, K2, FK2> where K2: Hash + Eq, FK2: Fn(&S) -> K2 {
+        IterativeDeepening {
+            initial_step: self.initial_step,
+            table_key: Some(key),
+            table: FxHashMap::default(),
+            iterations: Vec::new(),
+            stats: SearchStats::default(),
+        }
+    }
+
+    /// Run IDA*. The `successors` callback adds the next states with the cost of the move to
+    /// the end of the list, which also holds the other pending states. The `heuristic` must
+    /// never be more than the real cost from the state to a goal. The search gives up when the
+    /// limit is passed. It returns the cost and the path of the first goal found, with the
+    /// initial state and the goal included.
+    pub fn run<FS, FH, FG>(&mut self, limit: C, mut successors: FS, heuristic: FH, goal: FG) -> Option<(C, Vec<S>)>
+        where FS: FnMut(&S, &mut Vec<(S, C)>),
+              FH: Fn(&S) -> C,
+              FG: Fn(&S) -> bool {
+        self.iterations.clear();
+        self.stats = SearchStats::default();
+
+        let mut path = vec![self.initial_step.clone()];
+        if goal(&self.initial_step) {
+            return Some((C::zero(), path));
+        }
+
+        let mut threshold = heuristic(&self.initial_step);
+        while threshold <= limit {
+            path.clear();
+            path.push(self.initial_step.clone());
+            match self.iteration(threshold, &mut path, &mut successors, &heuristic, &goal) {
+                Iteration::Found(cost) => return Some((cost, path)),
+                Iteration::Exceeded(Some(next)) => threshold = next,
+                Iteration::Exceeded(None) => break,
+            }
+        }
+
+        None
+    }
+
+    fn iteration<FS, FH, FG>(&mut self, threshold: C, path: &mut Vec<S>, successors: &mut FS, heuristic: &FH, goal: &FG) -> Iteration<C>
+        where FS: FnMut(&S, &mut Vec<(S, C)>),
+              FH: Fn(&S) -> C,
+              FG: Fn(&S) -> bool {
+        let mut stats = IterationStats { limit: threshold, expanded: 1, pruned: 0 };
+        let mut next_threshold: Option<C> = None;
+        self.table.clear();
+
+        // The unvisited successors of every state on the path, in reverse, and where the ones
+        // of each state start.
+        let mut pending = Vec::with_capacity(64);
+        let mut frames = vec![0];
+        let mut costs = vec![C::zero()];
+        successors(&path[0], &mut pending);
+        pending.reverse();
+        self.stats.push_many(pending.len(), pending.len());
+
+        let res = loop {
+            let start = match frames.last() {
+                Some(start) => *start,
+                None => break Iteration::Exceeded(next_threshold),
+            };
+            if pending.len() == start {
+                frames.pop();
+                costs.pop();
+                path.pop();
+                continue;
+            }
+            let (state, step_cost) = self.stats.pop(pending.pop()).unwrap();
+
+            let cost = *costs.last().unwrap() + step_cost;
+            let estimate = cost + heuristic(&state);
+            if estimate > threshold {
+                stats.pruned += 1;
+                self.stats.bounded += 1;
+                if next_threshold.map(|t| estimate < t).unwrap_or(true) {
+                    next_threshold = Some(estimate);
+                }
+                continue;
+            }
+            if path.contains(&state) {
+                self.stats.duplicates += 1;
+                continue;
+            }
+            if let Some(table_key) = self.table_key.as_ref() {
+                match self.table.entry(table_key(&state)) {
+                    Entry::Occupied(entry) if *entry.get() <= cost => {
+                        stats.pruned += 1;
+                        self.stats.duplicates += 1;
+                        continue;
+                    }
+                    Entry::Occupied(mut entry) => {
+                        entry.insert(cost);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(cost);
+                    }
+                }
+            }
+
+            if goal(&state) {
+                path.push(state);
+                break Iteration::Found(cost);
+            }
+
+            stats.expanded += 1;
+            let start = pending.len();
+            successors(&state, &mut pending);
+            pending[start..].reverse();
+            self.stats.push_many(pending.len() - start, pending.len());
+
+            path.push(state);
+            costs.push(cost);
+            frames.push(start);
+        };
+
+        self.iterations.push(stats);
+        res
+    }
+
+    /// Get the stats of every iteration of the last run.
+    pub fn iterations(&self) -> &[IterationStats<C>] {
+        &self.iterations
+    }
+
+    /// Get the stats of all the iterations of the last run added up. The states over the limit
+    /// are counted as `bounded`, and the ones already on the path or in the transposition table
+    /// as `duplicates`.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+impl<S, K, FK> IterativeDeepening<S, usize, K, FK> where S: Eq + Clone, K: Hash + Eq, FK: Fn(&S) -> K {
+    /// Run an iterative deepening DFS up to the depth limit, where every move costs one. It
+    /// returns the depth and the path of the first goal found.
+    pub fn run_depth<FS, FG>(&mut self, max_depth: usize, mut successors: FS, goal: FG) -> Option<(usize, Vec<S>)>
+        where FS: FnMut(&S, &mut Vec<S>),
+              FG: Fn(&S) -> bool {
+        let mut next = Vec::new();
+        self.run(
+            max_depth,
+            |s, steps| {
+                successors(s, &mut next);
+                steps.extend(next.drain(..).map(|s| (s, 1)));
+            },
+            |_| 0,
+            goal,
+        )
+    }
+}
+
+/// NoTable is the key function type of an `IterativeDeepening` search without a transposition
+/// table.
+pub type NoTable<S> = fn(&S);
+
+/// Create an iterative deepening DFS, to run with `IterativeDeepening::run_depth`.
+pub fn iddfs<S>(initial_step: S) -> IterativeDeepening<S, usize, (), NoTable<S>> where S: Eq + Clone {
+    ida_star(initial_step)
+}
+
+/// Create an IDA* search, to run with `IterativeDeepening::run`.
+pub fn ida_star<S, C>(initial_step: S) -> IterativeDeepening<S, C, (), NoTable<S>> where S: Eq + Clone, C: Ord + Copy + Add<Output=C> + Zero {
+    IterativeDeepening {
+        initial_step,
+        table_key: None,
+        table: FxHashMap::default(),
+        iterations: Vec::new(),
+        stats: SearchStats::default(),
+    }
+}
+
+/// Meeting is where the two halves of a bidirectional search meet on the shortest path, and the
+/// total cost of the path.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Meeting<S, C> {
+    pub state: S,
+    pub cost: C,
+}
+
+/// The cost and the parent, if the paths are kept, of each state seen from one side.
+type Side<S, C> = FxHashMap<S, (C, Option<S>)>;
+
+/// Join the path from the start to the meeting state with the path from it to the goal.
+fn join_paths<S, C>(forward: &Side<S, C>, backward: &Side<S, C>, meeting: &S) -> Vec<S> where S: Hash + Eq + Clone {
+    let mut path = vec![meeting.clone()];
+    while let Some((_, Some(parent))) = forward.get(path.last().unwrap()) {
+        path.push(parent.clone());
+    }
+
+    path.reverse();
+    while let Some((_, Some(parent))) = backward.get(path.last().unwrap()) {
+        path.push(parent.clone());
+    }
+
+    path
+}
+
+/// BidirectionalBFS is a breadth first search from both the start and the goal at once, which
+/// visits far fewer states when the goal is known. Create it with `bidirectional_bfs`.
+pub struct BidirectionalBFS<S> {
+    start: S,
+    goal: S,
+    paths: bool,
+    forward: Side<S, usize>,
+    backward: Side<S, usize>,
+    meeting: Option<Meeting<S, usize>>,
+    stats: SearchStats,
+}
+
+impl<S> BidirectionalBFS<S> where S: Hash + Eq + Clone {
+    /// Keep the parents of the states so that `path` can be used after running.
+    pub fn with_paths(mut self) -> Self {
+        self.paths = true;
+        self
+    }
+
+    /// Run the search. The `forward` callback adds the states that can be reached from a state,
+    /// and `reverse` adds the states that the state can be reached from. The side with the
+    /// smaller frontier runs one level at a time, and the search stops after the first level
+    /// where the sides meet.
+    pub fn run<FF, FR>(&mut self, mut forward: FF, mut reverse: FR) -> Option<Meeting<S, usize>>
+        where FF: FnMut(&S, &mut Vec<S>),
+              FR: FnMut(&S, &mut Vec<S>) {
+        self.forward.clear();
+        self.backward.clear();
+        self.meeting = None;
+        self.stats = SearchStats::default();
+        self.stats.push_many(2, 2);
+
+        self.forward.insert(self.start.clone(), (0, None));
+        self.backward.insert(self.goal.clone(), (0, None));
+        if self.start == self.goal {
+            self.meeting = Some(Meeting { state: self.start.clone(), cost: 0 });
+            return self.meeting.clone();
+        }
+
+        let mut forward_level = vec![self.start.clone()];
+        let mut backward_level = vec![self.goal.clone()];
+        let mut next = Vec::with_capacity(16);
+        let mut depth = (0, 0);
+
+        while !forward_level.is_empty() && !backward_level.is_empty() {
+            let is_forward = forward_level.len() <= backward_level.len();
+            let other_len = if is_forward { backward_level.len() } else { forward_level.len() };
+            let (level, seen, other, cost) = if is_forward {
+                depth.0 += 1;
+                (&mut forward_level, &mut self.forward, &self.backward, depth.0)
+            } else {
+                depth.1 += 1;
+                (&mut backward_level, &mut self.backward, &self.forward, depth.1)
+            };
+
+            let mut next_level = Vec::with_capacity(level.len() * 2);
+            for (i, s) in level.iter().enumerate() {
+                self.stats.popped += 1;
+                if is_forward { forward(s, &mut next) } else { reverse(s, &mut next) }
+
+                for n in next.drain(..) {
+                    if seen.contains_key(&n) {
+                        self.stats.duplicates += 1;
+                        continue;
+                    }
+
+                    seen.insert(n.clone(), (cost, if self.paths { Some(s.clone()) } else { None }));
+                    if let Some((other_cost, _)) = other.get(&n) {
+                        if self.meeting.as_ref().map(|m| cost + other_cost < m.cost).unwrap_or(true) {
+                            self.meeting = Some(Meeting { state: n.clone(), cost: cost + other_cost });
+                        }
+                    }
+
+                    next_level.push(n);
+                    self.stats.push(level.len() - i - 1 + next_level.len() + other_len);
+                }
+            }
+
+            if self.meeting.is_some() {
+                break;
+            }
+
+            *level = next_level;
+        }
+
+        self.meeting.clone()
+    }
+
+    /// Get the shortest path from the start to the goal, both included, after running it
+    /// `with_paths`.
+    pub fn path(&self) -> Option<Vec<S>> {
+        match self.meeting.as_ref() {
+            Some(meeting) if self.paths => Some(join_paths(&self.forward, &self.backward, &meeting.state)),
+            _ => None,
+        }
+    }
+
+    /// Get the stats of the last run, with both sides added up.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+pub fn bidirectional_bfs<S>(start: S, goal: S) -> BidirectionalBFS<S> where S: Hash + Eq + Clone {
+    BidirectionalBFS {
+        start,
+        goal,
+        paths: false,
+        forward: FxHashMap::default(),
+        backward: FxHashMap::default(),
+        meeting: None,
+        stats: SearchStats::default(),
+    }
+}
+
+/// BidirectionalDijkstra is a dijkstra search from both the start and the goal at once. Create
+/// it with `bidirectional_dijkstra`.
+pub struct BidirectionalDijkstra<S, C> {
+    start: S,
+    goal: S,
+    paths: bool,
+    forward: Side<S, C>,
+    backward: Side<S, C>,
+    meeting: Option<Meeting<S, C>>,
+    stats: SearchStats,
+}
+
+/// An entry in the open set of `BidirectionalDijkstra`, where the lowest cost is the greatest.
+struct OpenStep<C, S> (C, S);
+
+impl<C: Ord, S> Eq for OpenStep<C, S> {}
+
+impl<C: Ord, S> PartialEq for OpenStep<C, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<C: Ord, S> PartialOrd for OpenStep<C, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Ord, S> Ord for OpenStep<C, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<S, C> BidirectionalDijkstra<S, C> where S: Hash + Eq + Clone, C: Ord + Copy + Add<Output=C> + Zero {
+    /// Keep the parents of the states so that `path` can be used after running.
+    pub fn with_paths(mut self) -> Self {
+        self.paths = true;
+        self
+    }
+
+    /// Run the search. The `forward` callback adds the states that can be reached from a state
+    /// with the cost of the move, and `reverse` adds the states that the state can be reached
+    /// from with the same cost. The side with the cheaper next state goes next, and the search
+    /// stops when no path through the open states can be cheaper than the best meeting.
+    pub fn run<FF, FR>(&mut self, mut forward: FF, mut reverse: FR) -> Option<Meeting<S, C>>
+        where FF: FnMut(&S, &mut Vec<(S, C)>),
+              FR: FnMut(&S, &mut Vec<(S, C)>) {
+        self.forward.clear();
+        self.backward.clear();
+        self.meeting = None;
+        self.stats = SearchStats::default();
+        self.stats.push_many(2, 2);
+
+        self.forward.insert(self.start.clone(), (C::zero(), None));
+        self.backward.insert(self.goal.clone(), (C::zero(), None));
+        if self.start == self.goal {
+            self.meeting = Some(Meeting { state: self.start.clone(), cost: C::zero() });
+            return self.meeting.clone();
+        }
+
+        let mut forward_open = BinaryHeap::with_capacity(128);
+        let mut backward_open = BinaryHeap::with_capacity(128);
+        forward_open.push(OpenStep(C::zero(), self.start.clone()));
+        backward_open.push(OpenStep(C::zero(), self.goal.clone()));
+        let mut next = Vec::with_capacity(16);
+
+        while let (Some(OpenStep(forward_cost, _)), Some(OpenStep(backward_cost, _))) = (forward_open.peek(), backward_open.peek()) {
+            let (forward_cost, backward_cost) = (*forward_cost, *backward_cost);
+            if let Some(meeting) = self.meeting.as_ref() {
+                if forward_cost + backward_cost >= meeting.cost {
+                    break;
+                }
+            }
+
+            let is_forward = forward_cost <= backward_cost;
+            let other_len = if is_forward { backward_open.len() } else { forward_open.len() };
+            let (open, seen, other) = if is_forward {
+                (&mut forward_open, &mut self.forward, &self.backward)
+            } else {
+                (&mut backward_open, &mut self.backward, &self.forward)
+            };
+
+            let OpenStep(cost, s) = self.stats.pop(open.pop()).unwrap();
+            if seen.get(&s).map(|(seen_cost, _)| cost > *seen_cost).unwrap_or(false) {
+                self.stats.duplicates += 1;
+                continue;
+            }
+
+            if is_forward { forward(&s, &mut next) } else { reverse(&s, &mut next) }
+            for (n, step_cost) in next.drain(..) {
+                let cost = cost + step_cost;
+                match seen.entry(n.clone()) {
+                    Entry::Occupied(entry) if entry.get().0 <= cost => {
+                        self.stats.duplicates += 1;
+                        continue;
+                    }
+                    Entry::Occupied(mut entry) => {
+                        entry.insert((cost, if self.paths { Some(s.clone()) } else { None }));
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert((cost, if self.paths { Some(s.clone()) } else { None }));
+                    }
+                }
+
+                if let Some((other_cost, _)) = other.get(&n) {
+                    if self.meeting.as_ref().map(|m| cost + *other_cost < m.cost).unwrap_or(true) {
+                        self.meeting = Some(Meeting { state: n.clone(), cost: cost + *other_cost });
+                    }
+                }
+
+                open.push(OpenStep(cost, n));
+                self.stats.push(open.len() + other_len);
+            }
+        }
+
+        self.meeting.clone()
+    }
+
+    /// Get the cheapest path from the start to the goal, both included, after running it
+    /// `with_paths`.
+    pub fn path(&self) -> Option<Vec<S>> {
+        match self.meeting.as_ref() {
+            Some(meeting) if self.paths => Some(join_paths(&self.forward, &self.backward, &meeting.state)),
+            _ => None,
+        }
+    }
+
+    /// Get the stats of the last run, with both sides added up.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+pub fn bidirectional_dijkstra<S, C>(start: S, goal: S) -> BidirectionalDijkstra<S, C> where S: Hash + Eq + Clone, C: Ord + Copy + Add<Output=C> + Zero {
+    BidirectionalDijkstra {
+        start,
+        goal,
+        paths: false,
+        forward: FxHashMap::default(),
+        backward: FxHashMap::default(),
+        meeting: None,
+        stats: SearchStats::default(),
+    }
+}
+
+const SHARDS: usize = 64;
+
+/// Sharded is a map or set that is split up by the hash of the key, so that threads rarely
+/// wait on each other to use it.
+struct Sharded<M> (Vec<Mutex<M>>);
+
+impl<M> Sharded<M> where M: Default {
+    fn new() -> Self {
+        Sharded((0..SHARDS).map(|_| Mutex::default()).collect())
+    }
+
+    fn shard<K: Hash + ?Sized>(&self, key: &K) -> MutexGuard<'_, M> {
+        // The high bits of the FxHash are the well mixed ones.
+        self.0[(hash_state(key) >> 58) as usize % SHARDS].lock().unwrap()
+    }
+}
+
+/// ParBFS is a breadth first search that runs every level in parallel. Create it with
+/// `par_bfs`.
+pub struct ParBFS<S> {
+    initial_step: S,
+}
+
+/// ParFrontier is the next level of a `ParBFS`, which the run callback adds steps to.
+pub struct ParFrontier<'a, S> {
+    seen: &'a Sharded<FxHashSet<S>>,
+    next: Vec<S>,
+    duplicates: usize,
+}
+
+impl<'a, S> ParFrontier<'a, S> where S: Hash + Eq + Clone {
+    pub fn try_add_step(&mut self, step: S) -> bool {
+        if self.seen.shard(&step).insert(step.clone()) {
+            self.next.push(step);
+            true
+        } else {
+            self.duplicates += 1;
+            false
+        }
+    }
+
+    #[inline]
+    pub fn add_step(&mut self, step: S) {
+        self.try_add_step(step);
+    }
+}
+
+impl<S> ParBFS<S> where S: Hash + Eq + Clone + Send + Sync {
+    /// Run the search like `Search::run`. The results of a level come in the order of the states
+    /// in it, but the level is only run when the results before it have been used up.
+    pub fn run<R, F>(self, f: F) -> ParRun<S, R, F> where R: Send, F: Fn(&mut ParFrontier<S>, &S) -> Option<R> + Sync {
+        let seen: Sharded<FxHashSet<S>> = Sharded::new();
+        seen.shard(&self.initial_step).insert(self.initial_step.clone());
+
+        ParRun {
+            seen,
+            level: vec![self.initial_step],
+            results: Vec::new().into_iter(),
+            f,
+            stats: SearchStats { pushed: 1, peak_frontier: 1, ..SearchStats::default() },
+        }
+    }
+}
+
+pub struct ParRun<S, R, F> {
+    seen: Sharded<FxHashSet<S>>,
+    level: Vec<S>,
+    results: std::vec::IntoIter<R>,
+    f: F,
+    stats: SearchStats,
+}
+
+impl<S, R, F> ParRun<S, R, F> {
+    /// Get the stats of the levels run so far. The peak frontier is the largest level.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+impl<S, R, F> Iterator for ParRun<S, R, F>
+    where S: Hash + Eq + Clone + Send + Sync,
+          R: Send,
+          F: Fn(&mut ParFrontier<S>, &S) -> Option<R> + Sync {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        loop {
+            if let Some(r) = self.results.next() {
+                return Some(r);
+            }
+            if self.level.is_empty() {
+                return None;
+            }
+
+            let seen = &self.seen;
+            let f = &self.f;
+            let (results, next, duplicates) = self.level.par_iter()
+                .fold(
+                    || (Vec::new(), ParFrontier { seen, next: Vec::new(), duplicates: 0 }),
+                    |(mut results, mut frontier), s| {
+                        if let Some(r) = f(&mut frontier, s) {
+                            results.push(r);
+                        }
+
+                        (results, frontier)
+                    },
+                )
+                .map(|(results, frontier)| (results, frontier.next, frontier.duplicates))
+                .reduce(
+                    || (Vec::new(), Vec::new(), 0),
+                    |(mut results, mut next, duplicates), (more_results, mut more_next, more_duplicates)| {
+                        results.extend(more_results);
+                        next.append(&mut more_next);
+                        (results, next, duplicates + more_duplicates)
+                    },
+                );
+
+            self.stats.popped += self.level.len();
+            self.stats.duplicates += duplicates;
+            self.stats.push_many(next.len(), next.len());
+            self.level = next;
+            self.results = results.into_iter();
+        }
+    }
+}
+
+/// Run a breadth first search where each level is run in parallel, with a seen set that is
+/// shared between the threads.
+pub fn par_bfs<S>(initial_step: S) -> ParBFS<S> where S: Hash + Eq + Clone + Send + Sync {
+    ParBFS { initial_step }
+}
+
+/// AtomicScore is a score that can be shared between threads through an atomic, which is what
+/// a `ParBranchAndBound` needs of its scores.
+pub trait AtomicScore: Copy + Ord + Send + Sync {
+    type Atomic: Send + Sync;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn new_atomic(self) -> Self::Atomic;
+    fn load(atomic: &Self::Atomic) -> Self;
+    fn fetch_max(atomic: &Self::Atomic, value: Self) -> Self;
+    fn fetch_min(atomic: &Self::Atomic, value: Self) -> Self;
+}
+
+macro_rules! atomic_score {
+    ($($t:ty => $atomic:ty),*) => {
+        $(
+            impl AtomicScore for $t {
+                type Atomic = $atomic;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn new_atomic(self) -> $atomic { <$atomic>::new(self) }
+                fn load(atomic: &$atomic) -> Self { atomic.load(AtomicOrdering::Acquire) }
+                fn fetch_max(atomic: &$atomic, value: Self) -> Self { atomic.fetch_max(value, AtomicOrdering::AcqRel) }
+                fn fetch_min(atomic: &$atomic, value: Self) -> Self { atomic.fetch_min(value, AtomicOrdering::AcqRel) }
+            }
+        )*
+    };
+}
+
+atomic_score!(
+    u8 => AtomicU8, u16 => AtomicU16, u32 => AtomicU32, u64 => AtomicU64, usize => AtomicUsize,
+    i8 => AtomicI8, i16 => AtomicI16, i32 => AtomicI32, i64 => AtomicI64, isize => AtomicIsize
+);
+
+/// ParBranchAndBound is a `BranchAndBound` that splits the search up between threads. The
+/// threads steal parts of each other's stacks, and share the best score through an atomic to
+/// prune against it. Create it with `par_maximize` or `par_minimize`.
+pub struct ParBranchAndBound<S, V, K, FK> {
+    initial_step: S,
+    maximize: bool,
+    memo_key: Option<FK>,
+    best: Option<(V, S)>,
+    expanded: usize,
+    stats: SearchStats,
+    key: PhantomData<K>,
+}
+
+/// The shared state of a `ParBranchAndBound` run.
+struct ParShared<S, V: AtomicScore, K, FK, FS, FSC, FB> {
+    maximize: bool,
+    memo_key: Option<FK>,
+    memo: Sharded<FxHashMap<K, V>>,
+    incumbent: V::Atomic,
+    found: AtomicBool,
+    best: Mutex<Option<(V, S)>>,
+    expanded: AtomicUsize,
+    stats: Mutex<SearchStats>,
+    successors: FS,
+    score: FSC,
+    bound: FB,
+}
+
+/// A thread hands off half its stack when it grows past this.
+const PAR_SPLIT: usize = 32;
+
+impl<S, V, K, FK, FS, FSC, FB> ParShared<S, V, K, FK, FS, FSC, FB>
+    where S: Clone + Send + Sync,
+          V: AtomicScore,
+          K: Hash + Eq + Send,
+          FK: Fn(&S) -> (K, V) + Sync,
+          FS: Fn(&S, &mut Vec<S>) + Sync,
+          FSC: Fn(&S) -> Option<V> + Sync,
+          FB: Fn(&S) -> V + Sync {
+    #[inline]
+    fn better(&self, a: V, b: V) -> bool {
+        if self.maximize { a > b } else { a < b }
+    }
+
+    fn explore<'s>(&'s self, scope: &rayon::Scope<'s>, mut stack: Vec<S>) {
+        let mut next = Vec::with_capacity(16);
+        let mut expanded = 0;
+        let mut stats = SearchStats::default();
+
+        while let Some(state) = stats.pop(stack.pop()) {
+            if let Some(score) = (self.score)(&state) {
+                let previous = if self.maximize {
+                    V::fetch_max(&self.incumbent, score)
+                } else {
+                    V::fetch_min(&self.incumbent, score)
+                };
+
+                // The incumbent starts at the worst score, so it can't tell if that score has
+                // been found yet.
+                if self.better(score, previous) || !self.found.load(AtomicOrdering::Acquire) {
+                    let mut best = self.best.lock().unwrap();
+                    if best.as_ref().map(|(best, _)| self.better(score, *best)).unwrap_or(true) {
+                        *best = Some((score, state.clone()));
+                    }
+                    self.found.store(true, AtomicOrdering::Release);
+                }
+            }
+
+            if self.found.load(AtomicOrdering::Acquire) && !self.better((self.bound)(&state), V::load(&self.incumbent)) {
+                stats.bounded += 1;
+                continue;
+            }
+
+            if let Some(memo_key) = self.memo_key.as_ref() {
+                let (key, value) = memo_key(&state);
+                let mut memo = self.memo.shard(&key);
+                match memo.entry(key) {
+                    Entry::Occupied(mut entry) => {
+                        if !self.better(value, *entry.get()) {
+                            stats.duplicates += 1;
+                            continue;
+                        }
+                        entry.insert(value);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(value);
+                    }
+                }
+            }
+
+            expanded += 1;
+            (self.successors)(&state, &mut next);
+            stats.push_many(next.len(), stack.len() + next.len());
+            stack.append(&mut next);
+
+            // The bottom of the stack is closer to the root, so it's the bigger half to hand off.
+            if stack.len() > PAR_SPLIT {
+                let rest = stack.split_off(stack.len() / 2);
+                let half = std::mem::replace(&mut stack, rest);
+                scope.spawn(move |scope| self.explore(scope, half));
+            }
+        }
+
+        self.expanded.fetch_add(expanded, AtomicOrdering::Relaxed);
+        self.stats.lock().unwrap().merge(stats);
+    }
+}
+
+impl<S, V, K, FK> ParBranchAndBound<S, V, K, FK>
+    where S: Clone + Send + Sync,
+          V: AtomicScore,
+          K: Hash + Eq + Send,
+          FK: Fn(&S) -> (K, V) + Sync {
+    /// Skip states like `BranchAndBound::memo`. The memo is shared between the threads.
+    pub fn memo<K2, FK2>(self, key: FK2) -> ParBranchAndBound<S, V, K2, FK2> where K2: Hash + Eq + Send, FK2: Fn(&S) -> (K2, V) + Sync {
+        ParBranchAndBound {
+            initial_step: self.initial_step,
+            maximize: self.maximize,
+            memo_key: Some(key),
+            best: None,
+            expanded: 0,
+            stats: SearchStats::default(),
+            key: PhantomData,
+        }
+    }
+
+    /// Run the search with the same callbacks as `BranchAndBound::run`, except that they're
+    /// called from several threads at once. Which of the equally good states ends up as the best
+    /// one may differ between runs.
+    pub fn run<FS, FSC, FB>(&mut self, successors: FS, score: FSC, bound: FB) -> Option<V>
+        where FS: Fn(&S, &mut Vec<S>) + Sync,
+              FSC: Fn(&S) -> Option<V> + Sync,
+              FB: Fn(&S) -> V + Sync {
+        let shared = ParShared {
+            maximize: self.maximize,
+            memo_key: self.memo_key.take(),
+            memo: Sharded::new(),
+            incumbent: if self.maximize { V::MIN.new_atomic() } else { V::MAX.new_atomic() },
+            found: AtomicBool::new(false),
+            best: Mutex::new(None),
+            expanded: AtomicUsize::new(0),
+            stats: Mutex::new(SearchStats { pushed: 1, peak_frontier: 1, ..SearchStats::default() }),
+            successors,
+            score,
+            bound,
+        };
+
+        let initial_step = self.initial_step.clone();
+        rayon::scope(|scope| shared.explore(scope, vec![initial_step]));
+
+        self.memo_key = shared.memo_key;
+        self.best = shared.best.into_inner().unwrap();
+        self.expanded = shared.expanded.into_inner();
+        self.stats = shared.stats.into_inner().unwrap();
+
+        self.best.as_ref().map(|(best, _)| *best)
+    }
+
+    /// Get the best score and the state it was found in, after running.
+    pub fn best(&self) -> Option<(V, &S)> {
+        self.best.as_ref().map(|(best, state)| (*best, state))
+    }
+
+    /// Get the number of states whose successors were generated.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Get the number of states skipped because of the bound or the memo.
+    pub fn pruned(&self) -> usize {
+        self.stats.bounded + self.stats.duplicates
+    }
+
+    /// Get the stats of the last run like `BranchAndBound::stats`. The peak frontier is the
+    /// largest stack of a single thread.
+    pub fn stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+/// Search for the highest score with branch and bound on all threads.
+pub fn par_maximize<S, V>(initial_step: S) -> ParBranchAndBound<S, V, (), NoMemo<S, V>> where S: Clone + Send + Sync, V: AtomicScore {
+    ParBranchAndBound {
+        initial_step,
+        maximize: true,
+        memo_key: None,
+        best: None,
+        expanded: 0,
+        stats: SearchStats::default(),
+        key: PhantomData,
+    }
+}
+
+/// Search for the lowest score with branch and bound on all threads.
+pub fn par_minimize<S, V>(initial_step: S) -> ParBranchAndBound<S, V, (), NoMemo<S, V>> where S: Clone + Send + Sync, V: AtomicScore {
+    ParBranchAndBound {
+        maximize: false,
+        ..par_maximize(initial_step)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
     use crate::geo::Point;
     use crate::grid2::{GetterGrid, VecGrid};
     use super::*;
@@ -334,6 +2133,23 @@ pub mod tests {
 
         let initial_step = WithCost(Point(1, 1), 0u32);
 
+        let maze_01 = parse_grid(MAZE_01);
+        let mut stats_search = bfs(initial_step).bounded(|s: &WithCost<Point<usize>, u32>| s.1 < 10);
+        assert_eq!((&mut stats_search).run(|search, WithCost(pos, cost)| {
+            for p in pos.cardinals_offset(1) {
+                if *maze_01.get(&p).unwrap() != b'#' {
+                    search.add_step(WithCost(p, cost + 1));
+                }
+            }
+
+            None::<()>
+        }).count(), 0);
+        let stats = stats_search.stats();
+        assert_eq!(stats.popped, stats.pushed);
+        assert_eq!(stats.pushed - stats.bounded, 10);
+        assert!(stats.duplicates > 0 && stats.peak_frontier > 0);
+        assert!(stats.to_string().starts_with("pushed "));
+
         let results_dijkstra = run_search(dijkstra(initial_step));
         let results_bfs = run_search(bfs(initial_step));
         let results_dfs = run_search(dfs(initial_step));
@@ -341,5 +2157,425 @@ pub mod tests {
         assert_eq!(results_dijkstra.as_slice(), &[('a', 51), ('b', 65), ('c', 71), ('z', 73)]);
         assert_eq!(results_bfs.as_slice(), &[('a', 51), ('b', 65), ('c', 71), ('z', 73)]);
         assert_eq!(results_dfs.len(), 4);
+
+        let index = GridIndex { width: 48, height: 12 };
+        assert_eq!(run_search(dijkstra_with(initial_step, DenseMap::new(index))), results_dijkstra);
+        assert_eq!(run_search(bfs_with(initial_step, DenseSet::new(index))), results_bfs);
+        assert_eq!(run_search(dfs_with(initial_step, DenseSet::new(index))).len(), 4);
+    }
+
+    #[test]
+    fn dense_storage() {
+        let mut seen = DenseSet::new(IndexFn(100, |n: &u32| if *n < 100 { Some(*n as usize) } else { None }));
+        assert!(seen.insert(&70));
+        assert!(!seen.insert(&70));
+        assert!(!seen.insert(&100));
+        assert!(seen.contains(&70) && !seen.contains(&71));
+        SeenSet::<u32>::clear(&mut seen);
+        assert!(!seen.contains(&70));
+
+        let mut costs = DenseMap::new(VoxelIndex { width: 2, height: 3, depth: 4 });
+        assert!(costs.insert(Vertex(1, 2, 3), 5));
+        assert!(!costs.insert(Vertex(2, 0, 0), 5));
+        assert_eq!(costs.get(&Vertex(1, 2, 3)), Some(&5));
+        assert_eq!(costs.get(&Vertex(0, 2, 3)), None);
+    }
+
+    #[test]
+    fn goal_adapters() {
+        const MAZE_02: &[u8] = b"#################\n#a......#...#.i.#\n#.#####.#.#.#.#.#\n#.#.y.#..z....#.#\n#.#############.#\n#.....#.t...#r..#\n#.#########...#.#\n#.........b.#.#x#\n#################\n";
+        const MAZE_03: &[u8] = b"#######\n#y...y#\n#..a..#\n#.....#\n#x...z#\n#######\n";
+
+        fn walk<S: Search<WithCost<Point<usize>, u32>>>(maze: &VecGrid<u8>, search: S) {
+            search.run(|search, WithCost(pos, cost)| {
+                for p in pos.cardinals_offset(1) {
+                    if *maze.get(&p).unwrap() != b'#' {
+                        search.add_step(WithCost(p, cost + 1));
+                    }
+                }
+
+                None::<()>
+            }).for_each(drop);
+        }
+
+        let maze = parse_grid(MAZE_02);
+        let mut search = bfs(WithCost(Point(1, 1), 0u32))
+            .collect_goals(|WithCost(p, _)| maze.get(p).unwrap().is_ascii_lowercase());
+        walk(&maze, &mut search);
+        let goals: Vec<(u8, u32)> = search.goals().iter().map(|WithCost(p, c)| (*maze.get(p).unwrap(), *c)).collect();
+        assert_eq!(goals, vec![(b'a', 0), (b'z', 10), (b'b', 15), (b'i', 17), (b'r', 20), (b't', 21), (b'x', 24)]);
+
+        let maze = parse_grid(MAZE_03);
+        for prune in [false, true] {
+            let search = dijkstra(WithCost(Point(3, 2), 0u32))
+                .optimal_goals(|WithCost(p, _)| *maze.get(p).unwrap() != b'.' && *maze.get(p).unwrap() != b'a', |s| s.1);
+            let mut search = if prune { search.prune_by_best() } else { search };
+            walk(&maze, &mut search);
+
+            let mut goals: Vec<Point<usize>> = search.goals().iter().map(|s| s.0).collect();
+            goals.sort();
+            assert_eq!(goals, vec![Point(1, 1), Point(5, 1)]);
+            assert_eq!(search.best_cost(), Some(&3));
+            assert!(search.stats().bounded > 0);
+        }
+    }
+
+    #[test]
+    fn paths_in_maze() {
+        let maze_01 = parse_grid(MAZE_01);
+
+        let mut search = bfs(Point(1usize, 1usize)).with_paths();
+        let goal = (&mut search).run(|search, pos| {
+            match *maze_01.get(pos).unwrap() {
+                b'#' => None,
+                b'a' => Some(*pos),
+                _ => {
+                    for p in pos.cardinals_offset(1) {
+                        search.add_step(p);
+                    }
+                    None
+                }
+            }
+        }).next().unwrap();
+
+        let path = search.path_to(&goal).unwrap();
+        assert_eq!(path.len(), 52);
+        assert_eq!(path.first(), Some(&Point(1, 1)));
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.windows(2).all(|w| w[0].manhattan_distance(&w[1]) == 1));
+        assert!(path.iter().all(|p| *maze_01.get(p).unwrap() != b'#'));
+        assert_eq!(search.paths_to(&goal), vec![path]);
+        assert_eq!(search.path_to(&Point(0, 0)), None);
+    }
+
+    #[test]
+    fn all_shortest_paths() {
+        fn run_search<S: Search<WithCost<Point<u32>, u32>>>(search: S) {
+            search.run(|search, WithCost(pos, cost)| {
+                if pos.0 < 2 {
+                    search.add_step(WithCost(Point(pos.0 + 1, pos.1), cost + 1));
+                }
+                if pos.1 < 2 {
+                    search.add_step(WithCost(Point(pos.0, pos.1 + 1), cost + 1));
+                }
+
+                None::<()>
+            }).count();
+        }
+
+        let mut search = bfs(WithCost(Point(0u32, 0u32), 0u32)).with_all_paths(|s| s.1);
+        run_search(&mut search);
+        let paths = search.paths_to(&WithCost(Point(2, 2), 0));
+        assert_eq!(paths.len(), 6);
+        assert!(paths.iter().all(|p| p.len() == 5));
+
+        let mut search = dijkstra(WithCost(Point(0u32, 0u32), 0u32)).with_all_paths(|s| s.1);
+        run_search(&mut search);
+        assert_eq!(search.paths_to(&WithCost(Point(1, 2), 0)).len(), 3);
+    }
+
+    #[test]
+    fn branch_and_bound_knapsack() {
+        // (weight, value) of the items, taken or left in order.
+        const ITEMS: &[(u32, u32)] = &[(12, 4), (2, 2), (1, 1), (1, 2), (4, 10), (3, 7), (9, 9)];
+        const CAPACITY: u32 = 15;
+
+        let successors = |&(i, weight, value): &(usize, u32, u32), next: &mut Vec<(usize, u32, u32)>| {
+            if let Some((w, v)) = ITEMS.get(i) {
+                next.push((i + 1, weight, value));
+                if weight + w <= CAPACITY {
+                    next.push((i + 1, weight + w, value + v));
+                }
+            }
+        };
+        let bound = |&(i, _, value): &(usize, u32, u32)| value + ITEMS[i..].iter().map(|(_, v)| v).sum::<u32>();
+
+        let mut exhaustive = maximize((0usize, 0u32, 0u32));
+        assert_eq!(exhaustive.run(successors, |s| Some(s.2), |_| u32::MAX), Some(22));
+
+        let mut bounded = maximize((0usize, 0u32, 0u32));
+        assert_eq!(bounded.run(successors, |s| Some(s.2), bound), Some(22));
+        assert_eq!(bounded.best().map(|(_, s)| s.2), Some(22));
+        assert!(bounded.pruned() > 0);
+        assert!(bounded.expanded() < exhaustive.expanded());
+        assert_eq!(bounded.stats().popped, bounded.expanded() + bounded.pruned());
+        assert_eq!((bounded.stats().pushed, bounded.stats().duplicates), (bounded.stats().popped, 0));
+
+        let mut memoized = maximize((0usize, 0u32, 0u32)).memo(|s| ((s.0, s.1), s.2));
+        assert_eq!(memoized.run(successors, |s| Some(s.2), bound), Some(22));
+
+        // The fewest items that weigh exactly the capacity.
+        let mut fewest = minimize((0usize, 0u32, 0u32));
+        let res = fewest.run(
+            |&(i, weight, count), next| {
+                if let Some((w, _)) = ITEMS.get(i) {
+                    next.push((i + 1, weight, count));
+                    if weight + w <= CAPACITY {
+                        next.push((i + 1, weight + w, count + 1));
+                    }
+                }
+            },
+            |s| if s.1 == CAPACITY { Some(s.2) } else { None },
+            |s| s.2,
+        );
+        assert_eq!(res, Some(2));
+    }
+
+    #[test]
+    fn beam_search_knapsack() {
+        const ITEMS: &[(u32, u32)] = &[(12, 4), (2, 2), (1, 1), (1, 2), (4, 10), (3, 7), (9, 9)];
+        const CAPACITY: u32 = 15;
+
+        let successors = |&(i, weight, value): &(usize, u32, u32), next: &mut Vec<(usize, u32, u32)>| {
+            if let Some((w, v)) = ITEMS.get(i) {
+                next.push((i + 1, weight, value));
+                if weight + w <= CAPACITY {
+                    next.push((i + 1, weight + w, value + v));
+                }
+            }
+        };
+        let bound = |&(i, _, value): &(usize, u32, u32)| value + ITEMS[i..].iter().map(|(_, v)| v).sum::<u32>();
+
+        // Wide enough to hold every state, it's exhaustive.
+        let mut wide = beam_search((0usize, 0u32, 0u32), 128);
+        assert_eq!(wide.run(successors, |s| s.2), Some(22));
+        assert_eq!(wide.beams().len(), ITEMS.len() + 1);
+        assert_eq!(wide.beams()[1], vec![(4, (1, 12, 4)), (0, (1, 0, 0))]);
+
+        let mut narrow = beam_search((0usize, 0u32, 0u32), 2);
+        let narrow_best = narrow.run(successors, |s| s.2).unwrap();
+        assert!(narrow_best < 22);
+        assert!(narrow.beams().iter().all(|beam| beam.len() <= 2));
+        assert_eq!(narrow.run(successors, |s| s.2), Some(narrow_best));
+
+        // Without dedupe, the states that leave the lightest items are in the beam twice.
+        let mut deduped = beam_search((0usize, 0u32, 0u32), 3).dedupe(|s| (s.0, s.1));
+        deduped.run(successors, |s| s.2);
+        let mut duplicated = beam_search((0usize, 0u32, 0u32), 3);
+        duplicated.run(successors, |s| s.2);
+        assert!(deduped.beams().iter().all(|beam| beam.iter().all(|(_, a)| beam.iter().filter(|(_, b)| (a.0, a.1) == (b.0, b.1)).count() == 1)));
+        assert!(duplicated.beams().iter().any(|beam| beam.iter().any(|(_, a)| beam.iter().filter(|(_, b)| (a.0, a.1) == (b.0, b.1)).count() > 1)));
+
+        let (value, state) = narrow.best().map(|(v, s)| (v, *s)).unwrap();
+        let mut seeded = maximize((0usize, 0u32, 0u32)).incumbent(value, state);
+        assert_eq!(seeded.run(successors, |s| Some(s.2), bound), Some(22));
+        let mut unseeded = maximize((0usize, 0u32, 0u32));
+        unseeded.run(successors, |s| Some(s.2), bound);
+        assert!(seeded.expanded() <= unseeded.expanded());
+
+        // Waiting is always possible, so it only ends at the depth limit.
+        let mut waiting = beam_search(0u32, 2).depth(6);
+        let res = waiting.run(|s, next| next.extend([*s, *s + 1]), |s| *s);
+        assert_eq!(res, Some(6));
+        assert_eq!(waiting.beams().len(), 7);
+        assert_eq!(waiting.best().map(|(_, s)| *s), Some(6));
+
+        let mut hopeless = maximize((0usize, 0u32, 0u32)).incumbent(100, state);
+        assert_eq!(hopeless.run(successors, |s| Some(s.2), bound), Some(100));
+        assert_eq!(hopeless.expanded(), 0);
+    }
+
+    #[test]
+    fn pareto_frontier() {
+        fn run_search<S: Search<(u32, u32, u32)>>(search: S) -> Vec<(u32, u32)> {
+            let mut res: Vec<(u32, u32)> = search.bounded(|s| s.0 <= 4).run(|search, &(t, a, b)| {
+                for (da, db) in [(0, 0), (2, 0), (0, 2), (1, 1)] {
+                    search.add_step((t + 1, a + da, b + db));
+                }
+
+                if t == 4 { Some((a, b)) } else { None }
+            }).collect();
+
+            res.sort();
+            res
+        }
+
+        let all = run_search(bfs((0, 0, 0)));
+        let frontier = run_search(bfs((0, 0, 0)).pareto(|s| s.0, |a, b| dominates(&[a.1, a.2], &[b.1, b.2])));
+
+        assert_eq!(all.len(), 25);
+        assert_eq!(frontier, (0..=8).map(|a| (a, 8 - a)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn par_bfs_maze() {
+        let maze_01 = parse_grid(MAZE_01);
+
+        let mut run = par_bfs(WithCost(Point(1usize, 1usize), 0u32))
+            .run(|search, WithCost(pos, cost)| {
+                let ch = *maze_01.get(pos).unwrap();
+                if ch == b'#' {
+                    return None;
+                }
+
+                for p in pos.cardinals_offset(1) {
+                    search.add_step(WithCost(p, cost + 1));
+                }
+
+                if ch != b'.' { Some((ch as char, *cost)) } else { None }
+            });
+        let results: Vec<(char, u32)> = run.by_ref().collect();
+
+        assert_eq!(results.as_slice(), &[('a', 51), ('b', 65), ('c', 71), ('z', 73)]);
+        assert_eq!(run.stats().pushed, run.stats().popped);
+        assert!(run.stats().duplicates > 0);
+    }
+
+    #[test]
+    fn par_branch_and_bound() {
+        let items: Vec<(u32, u32)> = (0..24u32).map(|i| (3 + (i * 7) % 11, 1 + (i * 5) % 13)).collect();
+        let capacity = 40;
+
+        let successors = |&(i, weight, value): &(usize, u32, u32), next: &mut Vec<(usize, u32, u32)>| {
+            if let Some((w, v)) = items.get(i) {
+                next.push((i + 1, weight, value));
+                if weight + w <= capacity {
+                    next.push((i + 1, weight + w, value + v));
+                }
+            }
+        };
+        let bound = |&(i, _, value): &(usize, u32, u32)| value + items[i..].iter().map(|(_, v)| v).sum::<u32>();
+
+        let mut sequential = maximize((0usize, 0u32, 0u32));
+        let expected = sequential.run(successors, |s| Some(s.2), bound);
+
+        let mut parallel = par_maximize((0usize, 0u32, 0u32));
+        assert_eq!(parallel.run(successors, |s| Some(s.2), bound), expected);
+        assert_eq!(parallel.best().map(|(_, s)| s.2), expected);
+        assert!(parallel.expanded() > 0 && parallel.pruned() > 0);
+        assert_eq!(parallel.stats().popped, parallel.expanded() + parallel.pruned());
+        assert_eq!(parallel.stats().pushed, parallel.stats().popped);
+
+        let mut memoized = par_maximize((0usize, 0u32, 0u32)).memo(|s| ((s.0, s.1), s.2));
+        assert_eq!(memoized.run(successors, |s| Some(s.2), bound), expected);
+
+        let mut lightest = par_minimize((0usize, 0u32, 0u32));
+        let res = lightest.run(successors, |s| if s.2 >= 30 { Some(s.1) } else { None }, |s| s.1);
+        let mut lightest = minimize((0usize, 0u32, 0u32));
+        assert_eq!(res, lightest.run(successors, |s| if s.2 >= 30 { Some(s.1) } else { None }, |s| s.1));
+
+        // The optimum is the starting value of the shared incumbent.
+        let nothing = |&(i, _, _): &(usize, u32, u32), next: &mut Vec<(usize, u32, u32)>| {
+            if i < 5 {
+                next.push((i + 1, 0, 0));
+            }
+        };
+        let expected = maximize((0usize, 0u32, 0u32)).run(nothing, |s| Some(s.2), |s| s.2);
+        assert_eq!(expected, Some(0));
+        assert_eq!(par_maximize((0usize, 0u32, 0u32)).run(nothing, |s| Some(s.2), |s| s.2), expected);
+        let mut heaviest = par_minimize((0usize, u32::MAX, 0u32));
+        assert_eq!(heaviest.run(|_, _| {}, |s| Some(s.1), |s| s.1), Some(u32::MAX));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn bidirectional_maze() {
+        let maze_01 = parse_grid(MAZE_01);
+        let open = |p: &Point<usize>| maze_01.get(p).map(|v| *v != b'#').unwrap_or(false);
+        let goal = Point(1usize, 10usize);
+        assert_eq!(maze_01.get(&goal), Some(&b'z'));
+
+        let mut search = bidirectional_bfs(Point(1usize, 1usize), goal).with_paths();
+        let neighbors = |p: &Point<usize>, next: &mut Vec<Point<usize>>| {
+            next.extend(p.cardinals_offset(1).into_iter().filter(|p| open(p)));
+        };
+        assert_eq!(search.run(neighbors, neighbors).map(|m| m.cost), Some(73));
+
+        let path = search.path().unwrap();
+        assert_eq!(path.len(), 74);
+        assert_eq!((path.first(), path.last()), (Some(&Point(1, 1)), Some(&goal)));
+        assert!(path.windows(2).all(|w| w[0].manhattan_distance(&w[1]) == 1 && open(&w[1])));
+        assert!(search.stats().pushed >= search.stats().popped && search.stats().duplicates > 0);
+
+        // Moving right costs 1, and everything else costs 2.
+        let weighted = |p: &Point<usize>, next: &mut Vec<(Point<usize>, u32)>, dir: i64| {
+            for n in p.cardinals_offset(1) {
+                if open(&n) {
+                    next.push((n, if (n.0 as i64 - p.0 as i64) * dir > 0 { 1 } else { 2 }));
+                }
+            }
+        };
+        let mut search = bidirectional_dijkstra(Point(1usize, 1usize), goal).with_paths();
+        let meeting = search.run(|p, next| weighted(p, next, 1), |p, next| weighted(p, next, -1)).unwrap();
+        let path = search.path().unwrap();
+        let cost: u32 = path.windows(2).map(|w| if w[1].0 > w[0].0 { 1 } else { 2 }).sum();
+        assert_eq!(meeting.cost, cost);
+        assert!(search.stats().pushed >= search.stats().popped && search.stats().peak_frontier > 0);
+
+        let expected = dijkstra(WithCost(Point(1usize, 1usize), 0u32)).run(|search, WithCost(p, c)| {
+            if *p == goal {
+                return Some(*c);
+            }
+
+            let mut next = Vec::new();
+            weighted(p, &mut next, 1);
+            for (n, step_cost) in next {
+                search.add_step(WithCost(n, c + step_cost));
+            }
+
+            None
+        }).next();
+        assert_eq!(Some(meeting.cost), expected);
+
+        assert_eq!(bidirectional_bfs(Point(1, 1), Point(5, 2)).run(neighbors, |_, _| {}), None);
+    }
+
+    #[test]
+    fn iterative_deepening() {
+        let neighbors = |p: &Point<u32>, next: &mut Vec<Point<u32>>| {
+            next.extend(p.cardinals_offset(1).into_iter().filter(|p| (1..6).contains(&p.0) && (1..6).contains(&p.1)));
+        };
+
+        let mut search = iddfs(Point(1u32, 1u32)).transpositions(|p| *p);
+        let (depth, path) = search.run_depth(20, neighbors, |p| *p == Point(5, 5)).unwrap();
+        assert_eq!(depth, 8);
+        assert_eq!(path.len(), 9);
+        assert_eq!(search.iterations().iter().map(|i| i.limit).collect::<Vec<_>>(), (0..=8).collect::<Vec<_>>());
+        assert_eq!(search.run_depth(7, neighbors, |p| *p == Point(5, 5)), None);
+
+        // The 8-puzzle, where the tile next to the gap moves into it.
+        fn moves(board: &[u8; 9], next: &mut Vec<([u8; 9], u32)>) {
+            let gap = board.iter().position(|t| *t == 0).unwrap();
+            for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                let (x, y) = ((gap % 3) as i32 + dx, (gap / 3) as i32 + dy);
+                if (0..3).contains(&x) && (0..3).contains(&y) {
+                    let mut board = *board;
+                    board.swap(gap, (y * 3 + x) as usize);
+                    next.push((board, 1));
+                }
+            }
+        }
+        fn manhattan(board: &[u8; 9]) -> u32 {
+            board.iter().enumerate()
+                .filter(|(_, t)| **t != 0)
+                .map(|(i, t)| {
+                    let goal = (*t - 1) as usize;
+                    ((i % 3).abs_diff(goal % 3) + (i / 3).abs_diff(goal / 3)) as u32
+                })
+                .sum()
+        }
+
+        let solved = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+        let start = [8, 6, 7, 2, 5, 4, 3, 0, 1];
+        let mut search = ida_star(start);
+        let (cost, path) = search.run(40, moves, manhattan, |b| *b == solved).unwrap();
+
+        let expected = bfs(WithCost(start, 0u32)).run(|search, WithCost(board, cost)| {
+            if *board == solved {
+                return Some(*cost);
+            }
+
+            let mut next = Vec::new();
+            moves(board, &mut next);
+            for (board, _) in next {
+                search.add_step(WithCost(board, cost + 1));
+            }
+
+            None
+        }).next();
+        assert_eq!(Some(cost), expected);
+        assert_eq!(path.len() as u32, cost + 1);
+        assert!(search.iterations().windows(2).all(|w| w[0].limit < w[1].limit));
+        assert_eq!(search.stats().bounded, search.iterations().iter().map(|i| i.pruned).sum::<usize>());
+        assert!(search.stats().duplicates > 0);
+    }
+}
@@ -0,0 +1,502 @@
+use std::marker::PhantomData;
+use crate::flood::{FloodMap, FloodSpace, Neighborhood};
+use crate::geo::{Point, Vertex};
+use crate::grid2::{ClearableGrid, CountableGrid, FillableGrid, FixedGrid, GetterGrid};
+
+/// The face neighbors in the same order as `Vertex::cardinals`.
+pub const CARDINALS_3D: &[Vertex<usize>; 6] = &[
+    Vertex(0, 0, usize::MAX),
+    Vertex(0, usize::MAX, 0),
+    Vertex(usize::MAX, 0, 0),
+    Vertex(1, 0, 0),
+    Vertex(0, 1, 0),
+    Vertex(0, 0, 1),
+];
+
+/// The face, edge and corner neighbors, ordered by z, then y, then x.
+pub const NEIGHBORS_3D: &[Vertex<usize>; 26] = &neighbor_offsets();
+
+const fn neighbor_offsets() -> [Vertex<usize>; 26] {
+    let mut res = [Vertex(0, 0, 0); 26];
+    let mut i = 0;
+    let mut n = 0usize;
+    while n < 27 {
+        if n != 13 {
+            res[i] = Vertex((n % 3).wrapping_sub(1), (n / 3 % 3).wrapping_sub(1), (n / 9).wrapping_sub(1));
+            i += 1;
+        }
+        n += 1;
+    }
+
+    res
+}
+
+#[inline]
+pub fn offset(pos: &Vertex<usize>, off: &Vertex<usize>) -> Vertex<usize> {
+    Vertex(pos.0.wrapping_add(off.0), pos.1.wrapping_add(off.1), pos.2.wrapping_add(off.2))
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+pub trait FixedVoxelGrid {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn depth(&self) -> usize;
+}
+
+pub trait GetterVoxelGrid<T> {
+    fn get(&self, pos: &Vertex<usize>) -> Option<&T>;
+}
+
+pub trait GetterMutVoxelGrid<T>: GetterVoxelGrid<T> {
+    fn get_mut(&mut self, pos: &Vertex<usize>) -> Option<&mut T>;
+}
+
+pub trait IterableVoxelGrid<T> {
+    /// Cells iterates over all grid cells with the positions, x first and z last.
+    fn cells(&self) -> VoxelIter<'_, T>;
+}
+
+/// Positions outside the grid are never counted, so a cell on the edge has fewer neighbors.
+pub trait NeighborCountVoxelGrid<T>: GetterVoxelGrid<T> {
+    fn count_cardinals(&self, pos: &Vertex<usize>, pred: &T) -> usize where T: Eq {
+        self.count_cardinals_where(pos, |v| v == pred)
+    }
+
+    fn count_cardinals_where<F>(&self, pos: &Vertex<usize>, pred: F) -> usize where F: Fn(&T) -> bool {
+        count_where(self, pos, CARDINALS_3D, pred)
+    }
+
+    fn count_neighbors(&self, pos: &Vertex<usize>, pred: &T) -> usize where T: Eq {
+        self.count_neighbors_where(pos, |v| v == pred)
+    }
+
+    fn count_neighbors_where<F>(&self, pos: &Vertex<usize>, pred: F) -> usize where F: Fn(&T) -> bool {
+        count_where(self, pos, NEIGHBORS_3D, pred)
+    }
+}
+
+impl<G, T> NeighborCountVoxelGrid<T> for G where G: GetterVoxelGrid<T> {}
+
+fn count_where<G, T, F>(grid: &G, pos: &Vertex<usize>, offsets: &[Vertex<usize>], pred: F) -> usize
+    where G: GetterVoxelGrid<T> + ?Sized, F: Fn(&T) -> bool {
+    offsets.iter()
+        .filter_map(|off| grid.get(&offset(pos, off)))
+        .filter(|v| pred(v))
+        .count()
+}
+
+pub trait SliceVoxelGrid<T>: GetterVoxelGrid<T> + FixedVoxelGrid + Sized {
+    /// Get a 2D view of the plane where the axis' coordinate is `index`. The remaining axes
+    /// keep their order, so a Z slice has x,y points and an X slice has y,z points.
+    fn slice(&self, axis: Axis, index: usize) -> Option<VoxelSlice<'_, Self, T>> {
+        let size = match axis {
+            Axis::X => self.width(),
+            Axis::Y => self.height(),
+            Axis::Z => self.depth(),
+        };
+
+        if index < size {
+            Some(VoxelSlice { grid: self, axis, index, spooky_ghost: PhantomData })
+        } else {
+            None
+        }
+    }
+}
+
+impl<G, T> SliceVoxelGrid<T> for G where G: GetterVoxelGrid<T> + FixedVoxelGrid {}
+
+pub struct VoxelSlice<'g, G, T> {
+    grid: &'g G,
+    axis: Axis,
+    index: usize,
+    spooky_ghost: PhantomData<T>,
+}
+
+impl<'g, G, T> VoxelSlice<'g, G, T> {
+    pub fn super_pos(&self, p: &Point<usize>) -> Vertex<usize> {
+        match self.axis {
+            Axis::X => Vertex(self.index, p.0, p.1),
+            Axis::Y => Vertex(p.0, self.index, p.1),
+            Axis::Z => Vertex(p.0, p.1, self.index),
+        }
+    }
+}
+
+impl<'g, G, T> FixedGrid for VoxelSlice<'g, G, T> where G: FixedVoxelGrid {
+    fn width(&self) -> usize {
+        match self.axis {
+            Axis::X => self.grid.height(),
+            Axis::Y | Axis::Z => self.grid.width(),
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self.axis {
+            Axis::X | Axis::Y => self.grid.depth(),
+            Axis::Z => self.grid.height(),
+        }
+    }
+}
+
+impl<'g, G, T> GetterGrid<T> for VoxelSlice<'g, G, T> where G: GetterVoxelGrid<T> + FixedVoxelGrid {
+    fn get(&self, pos: &Point<usize>) -> Option<&T> {
+        if pos.0 >= self.width() || pos.1 >= self.height() {
+            return None;
+        }
+
+        self.grid.get(&self.super_pos(pos))
+    }
+}
+
+pub struct ArrayVoxelGrid<T, const S: usize, const W: usize, const H: usize> {
+    data: [T; S],
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> Clone for ArrayVoxelGrid<T, S, W, H> where T: Clone {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> ArrayVoxelGrid<T, S, W, H> where T: Copy {
+    pub fn new_with(initial_value: T) -> Self {
+        assert_eq!(S % (W * H), 0);
+        Self { data: [initial_value; S] }
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> ArrayVoxelGrid<T, S, W, H> where T: Default + Copy {
+    pub fn new() -> Self {
+        Self::new_with(Default::default())
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> Default for ArrayVoxelGrid<T, S, W, H> where T: Default + Copy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> ArrayVoxelGrid<T, S, W, H> {
+    pub fn from_array(data: [T; S]) -> Self {
+        assert_eq!(S % (W * H), 0);
+        Self { data }
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> FixedVoxelGrid for ArrayVoxelGrid<T, S, W, H> {
+    fn width(&self) -> usize { W }
+    fn height(&self) -> usize { H }
+    fn depth(&self) -> usize { S / (W * H) }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> GetterVoxelGrid<T> for ArrayVoxelGrid<T, S, W, H> {
+    fn get(&self, pos: &Vertex<usize>) -> Option<&T> {
+        if pos.0 >= W || pos.1 >= H || pos.2 >= S / (W * H) {
+            return None;
+        }
+
+        self.data.get((pos.2 * H + pos.1) * W + pos.0)
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> GetterMutVoxelGrid<T> for ArrayVoxelGrid<T, S, W, H> {
+    fn get_mut(&mut self, pos: &Vertex<usize>) -> Option<&mut T> {
+        if pos.0 >= W || pos.1 >= H || pos.2 >= S / (W * H) {
+            return None;
+        }
+
+        self.data.get_mut((pos.2 * H + pos.1) * W + pos.0)
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> IterableVoxelGrid<T> for ArrayVoxelGrid<T, S, W, H> {
+    fn cells(&self) -> VoxelIter<'_, T> {
+        VoxelIter::new(&self.data, W, H)
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> CountableGrid<T> for ArrayVoxelGrid<T, S, W, H> where T: Eq {
+    fn count_occurrences_of(&self, pred: &T) -> usize {
+        self.data.iter().filter(|v| pred.eq(*v)).count()
+    }
+
+    fn count_occurrences_where<F>(&self, pred: F) -> usize where F: Fn(&T) -> bool {
+        self.data.iter().filter(|v| pred(*v)).count()
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> ClearableGrid for ArrayVoxelGrid<T, S, W, H> where T: Copy + Default {
+    fn clear(&mut self) {
+        self.data.fill(T::default());
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> FillableGrid<T> for ArrayVoxelGrid<T, S, W, H> where T: Copy {
+    fn fill(&mut self, v: T) {
+        self.data.fill(v);
+    }
+}
+
+#[derive(Clone)]
+pub struct VecVoxelGrid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> VecVoxelGrid<T> {
+    pub fn new_from(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert!(width > 0 && height > 0);
+        assert_eq!(data.len() % (width * height), 0);
+
+        Self { data, width, height }
+    }
+}
+
+impl<T> VecVoxelGrid<T> where T: Copy {
+    pub fn new_with(width: usize, height: usize, depth: usize, v: T) -> Self {
+        assert!(width > 0 && height > 0);
+
+        Self { data: vec![v; width * height * depth], width, height }
+    }
+}
+
+impl<T> VecVoxelGrid<T> where T: Copy + Default {
+    pub fn new(width: usize, height: usize, depth: usize) -> Self {
+        Self::new_with(width, height, depth, Default::default())
+    }
+}
+
+impl<T> FixedVoxelGrid for VecVoxelGrid<T> {
+    fn width(&self) -> usize { self.width }
+    fn height(&self) -> usize { self.height }
+    fn depth(&self) -> usize { self.data.len() / (self.width * self.height) }
+}
+
+impl<T> GetterVoxelGrid<T> for VecVoxelGrid<T> {
+    fn get(&self, pos: &Vertex<usize>) -> Option<&T> {
+        if pos.0 >= self.width || pos.1 >= self.height || pos.2 >= self.depth() {
+            return None;
+        }
+
+        self.data.get((pos.2 * self.height + pos.1) * self.width + pos.0)
+    }
+}
+
+impl<T> GetterMutVoxelGrid<T> for VecVoxelGrid<T> {
+    fn get_mut(&mut self, pos: &Vertex<usize>) -> Option<&mut T> {
+        if pos.0 >= self.width || pos.1 >= self.height || pos.2 >= self.depth() {
+            return None;
+        }
+
+        self.data.get_mut((pos.2 * self.height + pos.1) * self.width + pos.0)
+    }
+}
+
+impl<T> IterableVoxelGrid<T> for VecVoxelGrid<T> {
+    fn cells(&self) -> VoxelIter<'_, T> {
+        VoxelIter::new(&self.data, self.width, self.height)
+    }
+}
+
+impl<T> CountableGrid<T> for VecVoxelGrid<T> where T: Eq {
+    fn count_occurrences_of(&self, pred: &T) -> usize {
+        self.data.iter().filter(|v| pred.eq(*v)).count()
+    }
+
+    fn count_occurrences_where<F>(&self, pred: F) -> usize where F: Fn(&T) -> bool {
+        self.data.iter().filter(|v| pred(*v)).count()
+    }
+}
+
+impl<T> ClearableGrid for VecVoxelGrid<T> where T: Copy + Default {
+    fn clear(&mut self) {
+        self.data.fill(T::default());
+    }
+}
+
+impl<T> FillableGrid<T> for VecVoxelGrid<T> where T: Copy {
+    fn fill(&mut self, v: T) {
+        self.data.fill(v);
+    }
+}
+
+impl<T, const S: usize, const W: usize, const H: usize> FloodSpace<T> for ArrayVoxelGrid<T, S, W, H> {
+    type Pos = Vertex<usize>;
+    type Map<V: Copy> = VecVoxelGrid<V>;
+
+    #[inline]
+    fn at(&self, pos: &Vertex<usize>) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn for_each_neighbor<F: FnMut(Vertex<usize>)>(&self, pos: &Vertex<usize>, neighborhood: Neighborhood, f: F) {
+        for_each_neighbor(self, pos, neighborhood, f)
+    }
+
+    fn for_each_pos<F: FnMut(Vertex<usize>)>(&self, f: F) {
+        self.cells().map(|(p, _)| p).for_each(f)
+    }
+
+    fn new_map<V: Copy>(&self, v: V) -> VecVoxelGrid<V> {
+        VecVoxelGrid::new_with(W, H, self.depth(), v)
+    }
+}
+
+impl<T> FloodSpace<T> for VecVoxelGrid<T> {
+    type Pos = Vertex<usize>;
+    type Map<V: Copy> = VecVoxelGrid<V>;
+
+    #[inline]
+    fn at(&self, pos: &Vertex<usize>) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn for_each_neighbor<F: FnMut(Vertex<usize>)>(&self, pos: &Vertex<usize>, neighborhood: Neighborhood, f: F) {
+        for_each_neighbor(self, pos, neighborhood, f)
+    }
+
+    fn for_each_pos<F: FnMut(Vertex<usize>)>(&self, f: F) {
+        self.cells().map(|(p, _)| p).for_each(f)
+    }
+
+    fn new_map<V: Copy>(&self, v: V) -> VecVoxelGrid<V> {
+        VecVoxelGrid::new_with(self.width, self.height, self.depth(), v)
+    }
+}
+
+impl<V> FloodMap<V> for VecVoxelGrid<V> {
+    type Pos = Vertex<usize>;
+
+    #[inline]
+    fn at_mut(&mut self, pos: &Vertex<usize>) -> Option<&mut V> {
+        self.get_mut(pos)
+    }
+}
+
+fn for_each_neighbor<G, F>(grid: &G, pos: &Vertex<usize>, neighborhood: Neighborhood, mut f: F)
+    where G: FixedVoxelGrid, F: FnMut(Vertex<usize>) {
+    let offsets = match neighborhood {
+        Neighborhood::Cardinals => CARDINALS_3D.as_slice(),
+        Neighborhood::All => NEIGHBORS_3D.as_slice(),
+    };
+
+    for off in offsets {
+        let next = offset(pos, off);
+        if next.0 < grid.width() && next.1 < grid.height() && next.2 < grid.depth() {
+            f(next);
+        }
+    }
+}
+
+pub struct VoxelIter<'a, T> {
+    data: &'a [T],
+    pos: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a, T> VoxelIter<'a, T> {
+    fn new(data: &'a [T], width: usize, height: usize) -> Self {
+        VoxelIter { data, pos: 0, width, height }
+    }
+}
+
+impl<'a, T> Iterator for VoxelIter<'a, T> {
+    type Item = (Vertex<usize>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let v = self.data.get(self.pos)?;
+        let i = self.pos;
+        self.pos += 1;
+
+        Some((Vertex(i % self.width, (i / self.width) % self.height, i / (self.width * self.height)), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.data.len() - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flood::Flood;
+    use super::*;
+
+    #[test]
+    fn grid_basics() {
+        let mut grid: ArrayVoxelGrid<u8, 24, 2, 3> = ArrayVoxelGrid::new();
+        assert_eq!((grid.width(), grid.height(), grid.depth()), (2, 3, 4));
+
+        *grid.get_mut(&Vertex(1, 2, 3)).unwrap() = 7;
+        *grid.get_mut(&Vertex(0, 1, 2)).unwrap() = 5;
+        assert_eq!(grid.get(&Vertex(1, 2, 3)), Some(&7));
+        assert_eq!(grid.get(&Vertex(2, 0, 0)), None);
+        assert_eq!(grid.get(&Vertex(0, 0, 4)), None);
+        assert_eq!(grid.count_occurrences_where(|v| *v > 0), 2);
+
+        let cells: Vec<_> = grid.cells().filter(|(_, v)| **v > 0).collect();
+        assert_eq!(cells, vec![(Vertex(0, 1, 2), &5), (Vertex(1, 2, 3), &7)]);
+
+        let vec_grid = VecVoxelGrid::new_from(2, 3, grid.cells().map(|(_, v)| *v).collect());
+        assert_eq!(vec_grid.depth(), 4);
+        assert_eq!(vec_grid.get(&Vertex(0, 1, 2)), Some(&5));
+    }
+
+    #[test]
+    fn neighbors_and_slices() {
+        let mut grid = VecVoxelGrid::new_with(3, 3, 3, true);
+        assert_eq!(grid.count_cardinals(&Vertex(1, 1, 1), &true), 6);
+        assert_eq!(grid.count_neighbors(&Vertex(1, 1, 1), &true), 26);
+        assert_eq!(grid.count_cardinals(&Vertex(0, 0, 0), &true), 3);
+        assert_eq!(grid.count_neighbors(&Vertex(0, 0, 0), &true), 7);
+
+        *grid.get_mut(&Vertex(2, 1, 0)).unwrap() = false;
+        assert_eq!(grid.count_cardinals_where(&Vertex(1, 1, 0), |v| !*v), 1);
+        assert_eq!(grid.count_neighbors(&Vertex(1, 1, 1), &false), 1);
+
+        let z = grid.slice(Axis::Z, 0).unwrap();
+        assert_eq!((z.width(), z.height()), (3, 3));
+        assert_eq!(z.get(&Point(2, 1)), Some(&false));
+        let x = grid.slice(Axis::X, 2).unwrap();
+        assert_eq!(x.get(&Point(1, 0)), Some(&false));
+        assert_eq!(x.super_pos(&Point(1, 0)), Vertex(2, 1, 0));
+        let y = grid.slice(Axis::Y, 1).unwrap();
+        assert_eq!(y.get(&Point(2, 0)), Some(&false));
+        assert_eq!(y.get(&Point(2, 3)), None);
+        assert!(grid.slice(Axis::Y, 3).is_none());
+    }
+
+    #[test]
+    fn flood_fill() {
+        // A hollow 3x3x3 shell with the center cell sealed inside it.
+        let mut grid = VecVoxelGrid::new_with(5, 5, 5, false);
+        for x in 1..4 {
+            for y in 1..4 {
+                for z in 1..4 {
+                    *grid.get_mut(&Vertex(x, y, z)).unwrap() = Vertex(x, y, z) != Vertex(2, 2, 2);
+                }
+            }
+        }
+
+        let flood = Flood::new(&grid).passable(|v| !*v);
+        let outside = flood.fill(Vertex(0, 0, 0));
+        assert_eq!(outside.count_occurrences_of(&true), 125 - 27);
+        assert_eq!(outside.get(&Vertex(2, 2, 2)), Some(&false));
+
+        let (_, sizes) = flood.components();
+        assert_eq!(sizes, vec![98, 1]);
+        assert_eq!(*flood.distances(Vertex(0, 0, 0)).get(&Vertex(0, 4, 4)).unwrap(), Some(8));
+        assert_eq!(*flood.neighborhood(Neighborhood::All).distances(Vertex(0, 0, 0)).get(&Vertex(0, 4, 4)).unwrap(), Some(4));
+    }
+}
@@ -1,5 +1,5 @@
-use common::geo::Point;
-use common::grid::ArrayGrid;
+use common::geo::{Point, Rect};
+use common::grid2::{ArrayGrid, CountableGrid, IterableSliceGrid, RectGrid};
 use common::parse;
 
 common::day!(parse, part1, part2, 10000, 100, 50);
@@ -17,53 +17,51 @@ fn parse(data: &[u8]) -> Vec<Line> {
 }
 
 fn part1(input: &[Line]) -> usize {
-    let mut grid: ArrayGrid<bool, 1000000> = ArrayGrid::new_arr(1000, false);
+    let mut grid: ArrayGrid<bool, 1000000, 1000> = ArrayGrid::new_with(false);
 
     for Line{op, p1, p2} in input.iter() {
         match op {
             Operation::TurnOn => {
-                grid.fill_rect(*p1, *p2, true);
+                grid.fill_rect(&Rect(*p1, *p2), true);
             }
             Operation::TurnOff => {
-                grid.fill_rect(*p1, *p2, false);
+                grid.fill_rect(&Rect(*p1, *p2), false);
             }
             Operation::Toggle => {
-                grid.map_rect(*p1, *p2, |v, _| {
-                    *v = !*v;
-                });
+                grid.toggle_rect(&Rect(*p1, *p2));
             }
         }
     }
 
-    grid.iter().filter(|(_, v)| **v).count()
+    grid.count_occurrences_of(&true)
 }
 
 fn part2(input: &[Line]) -> u64 {
-    let mut grid: ArrayGrid<u64, 1000000> = ArrayGrid::new_arr(1000, 0);
+    let mut grid: ArrayGrid<u64, 1000000, 1000> = ArrayGrid::new_with(0);
 
     for Line{op, p1, p2} in input.iter() {
         match op {
             Operation::TurnOn => {
-                grid.map_rect(*p1, *p2, |v, _| {
+                grid.map_rect(&Rect(*p1, *p2), |v, _| {
                     *v += 1;
                 });
             }
             Operation::TurnOff => {
-                grid.map_rect(*p1, *p2, |v, _| {
+                grid.map_rect(&Rect(*p1, *p2), |v, _| {
                     if *v > 0 {
                         *v -= 1;
                     }
                 });
             }
             Operation::Toggle => {
-                grid.map_rect(*p1, *p2, |v, _| {
+                grid.map_rect(&Rect(*p1, *p2), |v, _| {
                     *v += 2;
                 });
             }
         }
     }
 
-    grid.iter().map(|(_, v)| *v).sum()
+    grid.cells().map(|(_, v)| *v).sum()
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -1,117 +1,44 @@
 use rustc_hash::FxHashMap;
+use common::memo::memo_with;
 use common::parse;
+use common::search2::{DenseMap, IndexFn};
 
 common::day!(parse, part1, part2, 1000, 100, 100);
 
 fn part1(wires: &[Wire]) -> u16 {
-    resolve_all(wires, 0)[0]
+    resolve_all(wires)[0]
 }
 
 fn part2(wires: &[Wire]) -> u16 {
     let mut wires2 = wires.to_vec();
 
-    let a = resolve_all(wires, 0)[0];
+    let a = resolve_all(wires)[0];
     wires2[1].input = Signal::DIRECT(Operand::Number(a));
 
-    resolve_all(&wires2, 0)[0]
+    resolve_all(&wires2)[0]
 }
 
-fn resolve_all(wires: &[Wire], stopper: usize) -> Vec<u16> {
-    let mut res = vec![0; wires.len()];
-    let mut resolved = vec![false; wires.len()];
-    let mut remaining = res.len();
-
-    while remaining > 0 {
-        for (i, wire) in wires.iter().enumerate() {
-            if resolved[i] {
-                continue;
-            }
-
-            let mut found = false;
-            match wire.input {
-                Signal::None => {
-                    found = true
-                }
-
-                Signal::DIRECT(j) => {
-                    if let Some(j) = resolve_dependency(&res, &resolved, j) {
-                        res[i] = j;
-                        found = true
-                    }
-                }
-
-                Signal::NOT(j) => {
-                    if let Some(j) = resolve_dependency(&res, &resolved, j) {
-                        res[i] = !j;
-                        found = true
-                    }
-                }
-
-                Signal::AND(j, k) => {
-                    if let Some((j, k)) = resolve_dependencies(&res, &resolved, j, k) {
-                        res[i] = j & k;
-                        found = true
-                    }
-                }
-
-                Signal::OR(j, k) => {
-                    if let Some((j, k)) = resolve_dependencies(&res, &resolved, j, k) {
-                        res[i] = j | k;
-                        found = true
-                    }
-                }
-
-                Signal::LSHIFT(j, k) => {
-                    if let Some((j, k)) = resolve_dependencies(&res, &resolved, j, k) {
-                        res[i] = j << k;
-                        found = true
-                    }
-                }
-
-                Signal::RSHIFT(j, k) => {
-                    if let Some((j, k)) = resolve_dependencies(&res, &resolved, j, k) {
-                        res[i] = j >> k;
-                        found = true
-                    }
-                }
-            }
-
-            if found {
-                if i == stopper {
-                    return res;
-                }
-
-                resolved[i] = true;
-                remaining -= 1;
-            }
-        }
-    }
-
-
-    res
-}
-
-fn resolve_dependency(res: &[u16], resolved: &[bool], op: Operand) -> Option<u16> {
-    match op {
-        Operand::Number(n) => Some(n),
-        Operand::Wire(j) => {
-            if resolved[j] {
-                Some(res[j])
-            } else {
-                None
+fn resolve_all(wires: &[Wire]) -> Vec<u16> {
+    let mut memo = memo_with(DenseMap::new(IndexFn(wires.len(), |i: &usize| Some(*i))));
+
+    (0..wires.len())
+        .map(|i| memo.solve(i, |resolve, i| {
+            let mut operand = |op: Operand| match op {
+                Operand::Number(n) => n,
+                Operand::Wire(j) => resolve(j),
+            };
+
+            match wires[*i].input {
+                Signal::None => 0,
+                Signal::DIRECT(j) => operand(j),
+                Signal::NOT(j) => !operand(j),
+                Signal::AND(j, k) => operand(j) & operand(k),
+                Signal::OR(j, k) => operand(j) | operand(k),
+                Signal::LSHIFT(j, k) => operand(j) << operand(k),
+                Signal::RSHIFT(j, k) => operand(j) >> operand(k),
             }
-        }
-    }
-}
-
-fn resolve_dependencies(res: &[u16], resolved: &[bool], op_1: Operand, op_2: Operand) -> Option<(u16, u16)> {
-    if let Some(v1) = resolve_dependency(res, resolved, op_1) {
-        if let Some(v2) = resolve_dependency(res, resolved, op_2) {
-            return Some((v1, v2));
-        }
-    }
-
-    None
+        }))
+        .collect()
 }
 
 fn parse(input: &[u8]) -> Vec<Wire> {
@@ -309,7 +236,7 @@ i -> z
     #[test]
     fn test_run_example() {
         let wires = parse(EXAMPLE_01);
-        assert_eq!(resolve_all(&wires, 1000), &[
+        assert_eq!(resolve_all(&wires), &[
             0,
             0,
             123,
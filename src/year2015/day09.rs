@@ -1,7 +1,6 @@
-use common::graph::Graph;
+use common::graph::{Graph, KeyedGraph};
 use common::parse;
-use common::search::{Dijkstra};
-use common::search::DijkstraResult::{Continue, Success};
+use common::search2::{dijkstra, Search, WithCost};
 
 common::day!(parse, part1, part2, 10000, 100, 100);
 
@@ -9,28 +8,28 @@ pub fn part1(graph: &Graph<&[u8], u32>) -> u32 {
     let mut min = u32::MAX;
     let goal_mask = (1 << graph.len() as u32) - 1u32;
 
-    let mut dijkstra = Dijkstra::new(true, true);
-
     for i in 0..graph.len() {
         let initial_mask = 1u32 << i as u32;
 
-        let res = dijkstra.run((i, initial_mask), 0, |(pos, visit_mask)| {
-            if *visit_mask == goal_mask {
-                Success
-            } else {
-                Continue(graph.edges(*pos).unwrap().filter_map(|(new_pos, distance)| {
+        let res = dijkstra(WithCost((i, initial_mask), 0u32))
+            .run(|search, WithCost((pos, visit_mask), cost)| {
+                if *visit_mask == goal_mask {
+                    return Some(*cost);
+                }
+
+                for (new_pos, distance) in graph.edges(*pos).unwrap() {
                     let city_mask = 1 << *new_pos as u32;
 
                     if visit_mask & city_mask == 0 {
-                        Some((*distance, 0, (*new_pos, visit_mask | city_mask)))
-                    } else {
-                        None
+                        search.add_step(WithCost((*new_pos, visit_mask | city_mask), cost + *distance));
                     }
-                }).collect())
-            }
-        });
+                }
+
+                None
+            })
+            .next();
 
-        if let Some((s, _)) = res {
+        if let Some(s) = res {
             if s < min {
                 min = s;
             }
@@ -44,28 +43,30 @@ pub fn part2(graph: &Graph<&[u8], u32>) -> u32 {
     let mut min = 0i32;
     let goal_mask = (1 << graph.len() as u32) - 1u32;
 
-    let mut dijkstra = Dijkstra::new(false, false);
-
     for i in 0..graph.len() {
         let initial_mask = 1u32 << i as u32;
 
-        let res = dijkstra.run((i, initial_mask), 0i32, |(pos, visit_mask)| {
-            if *visit_mask == goal_mask {
-                Success
-            } else {
-                Continue(graph.edges(*pos).unwrap().filter_map(|(new_pos, distance)| {
+        // The negative distances make dijkstra find the longest route, but only after every
+        // route has been tried.
+        let res = dijkstra(WithCost((i, initial_mask), 0i32))
+            .run(|search, WithCost((pos, visit_mask), cost)| {
+                if *visit_mask == goal_mask {
+                    return Some(*cost);
+                }
+
+                for (new_pos, distance) in graph.edges(*pos).unwrap() {
                     let city_mask = 1 << *new_pos as u32;
 
                     if visit_mask & city_mask == 0 {
-                        Some((-(*distance as i32), 0, (*new_pos, visit_mask | city_mask)))
-                    } else {
-                        None
+                        search.add_step(WithCost((*new_pos, visit_mask | city_mask), cost - *distance as i32));
                     }
-                }).collect())
-            }
-        });
+                }
+
+                None
+            })
+            .min();
 
-        if let Some((s, _)) = res {
+        if let Some(s) = res {
             if s < min {
                 min = s;
             }
@@ -76,7 +77,7 @@ pub fn part2(graph: &Graph<&[u8], u32>) -> u32 {
 }
 
 pub fn parse(mut input: &[u8]) -> Graph<&[u8], u32> {
-    let mut graph = Graph::new();
+    let mut graph = KeyedGraph::new(|city: &&[u8]| *city);
     while let Some((city_a, _, city_b, _, dist, _, next)) = common::parse_all!(
         input,
         parse::word,
@@ -93,7 +94,7 @@ pub fn parse(mut input: &[u8]) -> Graph<&[u8], u32> {
         input = next
     }
 
-    graph
+    graph.into_graph()
 }
 
 #[cfg(test)]
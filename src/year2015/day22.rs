@@ -1,6 +1,5 @@
-use smallvec::SmallVec;
 use common::parse2;
-use common::search::{Dijkstra, DijkstraResult};
+use common::search2;
 
 common::day!(parse, part1, part2, 100000, 500, 100);
 
@@ -76,100 +75,94 @@ fn part2(input: &BossStats) -> i32 {
 }
 
 fn run_puzzle(input: &BossStats, hard_mode: bool, initial_state: GameState) -> i32 {
-    let mut d = Dijkstra::new(true, true);
-    let (res, _) = d.run(initial_state, 0,  |state| {
-        if state.boss_health <= 0 {
-            return DijkstraResult::Success
-        }
-        if state.player_health <= 0 {
-            return DijkstraResult::DeadEnd
-        }
-
-        let mut next_steps: SmallVec<[(i32, i32, GameState); 16]> = SmallVec::new();
-        let mut next_state = *state;
-
-        if hard_mode && state.player_turn {
-            next_state.player_health -= 1;
-            if next_state.player_health <= 0 {
-                return DijkstraResult::DeadEnd
-            }
-        }
-
-        if next_state.poison_turns > 0 {
-            next_state.boss_health -= POISON_DAMAGE;
-            next_state.poison_turns -= 1;
-        }
-        if next_state.recharge_turns > 0 {
-            next_state.player_mana += RECHARGE_MANA;
-            next_state.recharge_turns -= 1;
-        }
-        let boss_damage = if next_state.shield_turns > 0 {
-            next_state.shield_turns -= 1;
-
-            if next_state.shield_turns > 0 {
-                input.dmg - SHIELD_ARMOR
-            } else {
-                input.dmg
-            }
-        } else {
-            input.dmg
-        };
-
-        next_state.player_turn = !next_state.player_turn;
-
-        if state.player_turn {
-            if next_state.player_mana > MISSILE_COST {
-                let mut spell = next_state;
-                spell.boss_health -= MISSILE_DAMAGE;
-                spell.player_mana -= MISSILE_COST;
-
-                next_steps.push((MISSILE_COST, 0, spell));
-            }
-            if next_state.player_mana > DRAIN_COST {
-                let mut spell = next_state;
-                spell.player_health += DRAIN_HEAL;
-                spell.boss_health -= DRAIN_DAMAGE;
-                spell.player_mana -= DRAIN_COST;
-
-                next_steps.push((DRAIN_COST, 0, spell));
-            }
-            if next_state.player_mana > SHIELD_COST {
-                let mut spell = next_state;
-                spell.shield_turns = SHIELD_DURATION;
-                spell.player_mana -= SHIELD_COST;
-
-                next_steps.push((SHIELD_COST, 0, spell));
-            }
-            if next_state.player_mana > RECHARGE_COST {
-                let mut spell = next_state;
-                spell.recharge_turns = RECHARGE_DURATION;
-                spell.player_mana -= RECHARGE_COST;
-
-                next_steps.push((RECHARGE_COST, 0, spell));
-            }
-            if next_state.player_mana > POISON_COST {
-                let mut spell = next_state;
-                spell.poison_turns = POISON_DURATION;
-                spell.player_mana -= POISON_COST;
-
-                next_steps.push((POISON_COST, 0, spell));
-            }
-
-            if next_steps.len() == 0 {
-                return DijkstraResult::DeadEnd
-            }
-        } else {
-            if next_state.boss_health > 0 {
-                next_state.player_health -= boss_damage;
-            }
-
-            next_steps.push((0, 0, next_state));
-        }
-
-        DijkstraResult::Continue(next_steps)
-    }).unwrap();
-
-    res
+    search2::minimize((initial_state, 0))
+        .memo(|(state, spent)| (*state, *spent))
+        .run(
+            |&(state, spent), next_steps| {
+                if state.boss_health <= 0 || state.player_health <= 0 {
+                    return
+                }
+
+                let mut next_state = state;
+
+                if hard_mode && state.player_turn {
+                    next_state.player_health -= 1;
+                    if next_state.player_health <= 0 {
+                        return
+                    }
+                }
+
+                if next_state.poison_turns > 0 {
+                    next_state.boss_health -= POISON_DAMAGE;
+                    next_state.poison_turns -= 1;
+                }
+                if next_state.recharge_turns > 0 {
+                    next_state.player_mana += RECHARGE_MANA;
+                    next_state.recharge_turns -= 1;
+                }
+                let boss_damage = if next_state.shield_turns > 0 {
+                    next_state.shield_turns -= 1;
+
+                    if next_state.shield_turns > 0 {
+                        input.dmg - SHIELD_ARMOR
+                    } else {
+                        input.dmg
+                    }
+                } else {
+                    input.dmg
+                };
+
+                next_state.player_turn = !next_state.player_turn;
+
+                if state.player_turn {
+                    if next_state.player_mana > MISSILE_COST {
+                        let mut spell = next_state;
+                        spell.boss_health -= MISSILE_DAMAGE;
+                        spell.player_mana -= MISSILE_COST;
+
+                        next_steps.push((spell, spent + MISSILE_COST));
+                    }
+                    if next_state.player_mana > DRAIN_COST {
+                        let mut spell = next_state;
+                        spell.player_health += DRAIN_HEAL;
+                        spell.boss_health -= DRAIN_DAMAGE;
+                        spell.player_mana -= DRAIN_COST;
+
+                        next_steps.push((spell, spent + DRAIN_COST));
+                    }
+                    if next_state.player_mana > SHIELD_COST {
+                        let mut spell = next_state;
+                        spell.shield_turns = SHIELD_DURATION;
+                        spell.player_mana -= SHIELD_COST;
+
+                        next_steps.push((spell, spent + SHIELD_COST));
+                    }
+                    if next_state.player_mana > RECHARGE_COST {
+                        let mut spell = next_state;
+                        spell.recharge_turns = RECHARGE_DURATION;
+                        spell.player_mana -= RECHARGE_COST;
+
+                        next_steps.push((spell, spent + RECHARGE_COST));
+                    }
+                    if next_state.player_mana > POISON_COST {
+                        let mut spell = next_state;
+                        spell.poison_turns = POISON_DURATION;
+                        spell.player_mana -= POISON_COST;
+
+                        next_steps.push((spell, spent + POISON_COST));
+                    }
+                } else {
+                    if next_state.boss_health > 0 {
+                        next_state.player_health -= boss_damage;
+                    }
+
+                    next_steps.push((next_state, spent));
+                }
+            },
+            |(state, spent)| if state.boss_health <= 0 { Some(*spent) } else { None },
+            |(_, spent)| *spent,
+        )
+        .unwrap()
 }
 
 #[test]
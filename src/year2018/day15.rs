@@ -1,7 +1,8 @@
 use common::aoc::Day;
 use common::geo::Point;
-use common::grid2::{FixedGrid, GetterMutGrid, IterableSliceGrid, RowGrid, VecGrid};
-use common::search::{Dijkstra, DijkstraResult};
+use common::grid2::{FixedGrid, GetterGrid, GetterMutGrid, IterableSliceGrid, RowGrid, VecGrid};
+use common::legend::{Legend, Symbol, Symbols};
+use common::search2::{dijkstra, Search, WithCost};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let grid = day.run_parse(1000, || parse_grid(input));
@@ -14,24 +15,19 @@ pub fn main(day: &mut Day, input: &[u8]) {
 }
 
 fn parse_grid(data: &[u8]) -> VecGrid<Piece> {
+    let mut grid: VecGrid<Piece> = Symbols.parse_grid(data).unwrap();
+
     let mut next_id: u8 = 0;
+    for y in 0..grid.height() {
+        for piece in grid.row_mut(y).unwrap() {
+            if let Piece::Player { id, .. } = piece {
+                *id = next_id;
+                next_id += 1;
+            }
+        }
+    }
 
-    VecGrid::new_from(
-        data.iter().take_while(|b| **b != b'\n').count(),
-        data.iter()
-            .filter(|b| **b != b'\n')
-            .map(move |v| match *v {
-                b'#' => Piece::Wall,
-                b'.' => Piece::Ground,
-                b'G' | b'E' => {
-                    let player = Piece::Player { team: *v, hp: 200, id: next_id };
-                    next_id += 1;
-                    player
-                }
-                _ => unreachable!()
-            })
-            .collect(),
-    )
+    grid
 }
 
 fn part1<G>(grid: &G) -> u32 where G: GetterMutGrid<Piece> + RowGrid<Piece> + FixedGrid + Clone + IterableSliceGrid<Piece> {
@@ -58,7 +54,6 @@ fn part2<G>(grid: &G) -> u32 where G: GetterMutGrid<Piece> + RowGrid<Piece> + Fi
 #[derive(Clone)]
 struct Board<G> where G: GetterMutGrid<Piece> + RowGrid<Piece> + FixedGrid + Clone + IterableSliceGrid<Piece> {
     grid: G,
-    move_dijkstra: Dijkstra<(Point<usize>, Option<Point<usize>>), usize>,
     elves: u8,
     goblins: u8,
     elf_power: u8,
@@ -219,48 +214,32 @@ impl<G> Board<G> where G: GetterMutGrid<Piece> + RowGrid<Piece> + FixedGrid + Cl
     fn find_move(&mut self, pos: &Point<usize>) -> Option<Point<usize>> {
         let target_team = if self.grid.get(pos).unwrap().is_elf() { b'G' } else { b'E' };
 
-        self.move_dijkstra.run((*pos, None), 0, |(current_pos, first_move)| {
-            if let Some(mut piece) = self.grid.get(current_pos).copied() {
-                // Start position is always treated as ground.
-                if current_pos == pos {
-                    piece = Piece::Ground;
-                }
-
-                match piece {
-                    Piece::Ground => {
-                        let is_next_to_enemy = current_pos.cardinals()
-                            .into_iter()
-                            .find(|p2| self.grid.get(p2).unwrap().is_player_of_team(target_team))
-                            .is_some();
-                        if is_next_to_enemy {
-                            DijkstraResult::Success
-                        } else {
-                            DijkstraResult::Continue(
-                                current_pos.cardinals()
-                                    .into_iter()
-                                    .map(|p2| (1, 0, (p2, first_move.clone().or(Some(p2)))))
-                                    .collect()
-                            )
-                        }
-                    },
-                    _ => DijkstraResult::DeadEnd,
+        let is_next_to_enemy = |p: &Point<usize>| p.cardinals()
+            .into_iter()
+            .any(|p2| self.grid.get(&p2).unwrap().is_player_of_team(target_team));
+
+        // Every closest square next to an enemy is a goal, and the first move towards each of
+        // them is part of the state so that the reading order can pick among them.
+        let mut search = dijkstra(WithCost((*pos, None), 0u32))
+            .optimal_goals(|WithCost((p, _), _)| is_next_to_enemy(p), |s| s.1)
+            .prune_by_best();
+
+        (&mut search).run(|search, WithCost((current_pos, first_move), cost)| {
+            for p2 in current_pos.cardinals() {
+                if let Some(Piece::Ground) = self.grid.get(&p2) {
+                    search.add_step(WithCost((p2, first_move.or(Some(p2))), cost + 1));
                 }
-            } else {
-                DijkstraResult::DeadEnd
             }
-        });
 
-        let candidates = self.move_dijkstra.candidates();
+            None::<()>
+        }).for_each(drop);
 
-        if candidates.len() > 0 {
-            candidates.iter()
-                .min_by(|(ap, an), (bp, bn)| {
-                    ap.cmp(bp).then_with(|| an.unwrap().cmp(&bn.unwrap()))
-                })
-                .and_then(|(_, next_move)| *next_move)
-        } else {
-            None
-        }
+        search.goals().iter()
+            .map(|WithCost(goal, _)| goal)
+            .min_by(|(ap, an), (bp, bn)| {
+                ap.cmp(bp).then_with(|| an.cmp(bn))
+            })
+            .and_then(|(_, next_move)| *next_move)
     }
 
     fn new(grid: &G) -> Self {
@@ -283,7 +262,6 @@ impl<G> Board<G> where G: GetterMutGrid<Piece> + RowGrid<Piece> + FixedGrid + Cl
             elf_power: 3,
             everyone_stuck: false,
             grid: grid.clone(),
-            move_dijkstra: Dijkstra::new(true   , false),
         }
     }
 
@@ -293,14 +271,11 @@ impl<G> Board<G> where G: GetterMutGrid<Piece> + RowGrid<Piece> + FixedGrid + Cl
         let mut annotations = Vec::with_capacity(4);
         for y in 0..self.grid.height() {
             for x in 0..self.grid.width() {
-                match self.grid.get(&Point(x, y)).unwrap() {
-                    Piece::Player { team, hp, .. } => {
-                        annotations.push(format!("  {}({})", *team as char, *hp));
-                        res.push(*team as char)
-                    }
-                    Piece::Wall => res.push('#'),
-                    Piece::Ground => res.push('.'),
+                let piece = self.grid.get(&Point(x, y)).unwrap();
+                if let Piece::Player { team, hp, .. } = piece {
+                    annotations.push(format!("  {}({})", *team as char, *hp));
                 }
+                res.push(piece.to_symbol() as char);
             }
 
             for annotation in annotations.iter() {
@@ -332,8 +307,29 @@ impl Piece {
     fn is_elf(&self) -> bool { if let Piece::Player { team, .. } = self { *team == b'E' } else { false } }
 }
 
+impl Symbol for Piece {
+    /// Players get id 0, `parse_grid` numbers them.
+    fn from_symbol(symbol: u8) -> Option<Self> {
+        match symbol {
+            b'#' => Some(Piece::Wall),
+            b'.' => Some(Piece::Ground),
+            b'G' | b'E' => Some(Piece::Player { team: symbol, hp: 200, id: 0 }),
+            _ => None,
+        }
+    }
+
+    fn to_symbol(&self) -> u8 {
+        match self {
+            Piece::Player { team, .. } => *team,
+            Piece::Wall => b'#',
+            Piece::Ground => b'.',
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use common::legend::assert_grid_eq;
     use super::*;
 
     fn e(id: u8) -> Piece { Piece::Player { team: b'E', hp: 200, id } }
@@ -444,20 +440,7 @@ mod tests {
 ";
 
     fn util_render_grid(grid: &VecGrid<Piece>) -> Vec<u8> {
-        let mut res = Vec::with_capacity(grid.width() * grid.height() + grid.height());
-        for y in 0..grid.height() {
-            for x in 0..grid.width() {
-                match grid.get(&Point(x, y)).unwrap() {
-                    Piece::Player { team, .. } => res.push(*team),
-                    Piece::Wall => res.push(b'#'),
-                    Piece::Ground => res.push(b'.'),
-                }
-            }
-
-            res.push(b'\n');
-        }
-
-        res
+        Symbols.render_grid(grid).into_bytes()
     }
 
     #[test]
@@ -540,9 +523,7 @@ mod tests {
 
         for i in 1..=3usize {
             board.run_turn();
-            println!("{}", String::from_utf8_lossy(EXAMPLE_P1_MOVEMENT_3[i]));
-            println!("{}", String::from_utf8_lossy(&util_render_grid(&board.grid)));
-            assert_eq!(util_render_grid(&board.grid), EXAMPLE_P1_MOVEMENT_3[i]);
+            assert_grid_eq(&Symbols.render_grid(&board.grid), std::str::from_utf8(EXAMPLE_P1_MOVEMENT_3[i]).unwrap());
         }
     }
 
@@ -1,61 +1,69 @@
 use common::geo::Point;
-use common::graph::Graph;
-use common::grid2::{FixedGrid, GetterMutGrid, IterableSliceGrid};
+use common::graph::KeyedGraph;
+use common::grid2::{FixedGrid, GetterGrid, GetterMutGrid, IterableSliceGrid};
 use common::grid2::VecGrid;
-use common::search::{BFS, BFSResult, Dijkstra, DijkstraResult};
+use common::search2;
+use common::search2::{Search, WithCost};
 
 common::day!(parse, part1, part2, 100, 1000, 50);
 
-fn part1(graph: &Graph<u16, (u32, i32)>) -> u32 {
+fn part1(graph: &KeyedGraph<u16, (u32, i32), u16>) -> u32 {
     let aa = graph.find(&AA).unwrap();
     let zz = graph.find(&ZZ).unwrap();
 
-    let mut dijkstra = Dijkstra::new(true, true);
-    dijkstra.run(aa, 0u32, |index| {
-        if *index == zz {
-            DijkstraResult::Success
-        } else {
-            DijkstraResult::Continue(graph.edges(*index).unwrap().map(|(next_index, (move_cost, _))| {
-                (*move_cost, 0, *next_index)
-            }).collect())
-        }
-    }).unwrap().0
+    search2::dijkstra(WithCost(aa, 0u32))
+        .run(|search, WithCost(index, cost)| {
+            if *index == zz {
+                return Some(*cost);
+            }
+
+            for (next_index, (move_cost, _)) in graph.edges(*index).unwrap() {
+                search.add_step(WithCost(*next_index, cost + *move_cost));
+            }
+
+            None
+        })
+        .next()
+        .unwrap()
 }
 
-fn part2(graph: &Graph<u16, (u32, i32)>) -> u32 {
+fn part2(graph: &KeyedGraph<u16, (u32, i32), u16>) -> u32 {
     let aa = graph.find(&AA).unwrap();
     let zz = graph.find(&ZZ).unwrap();
 
-    let mut dijkstra = Dijkstra::new(true, false);
-    dijkstra.run((aa, 0), 0u32, |(index, level)| {
-        if *index == zz {
-            DijkstraResult::Success
-        } else {
-            DijkstraResult::Continue(graph.edges(*index).unwrap().filter_map(|(next_index, (move_cost, level_diff))| {
-                let portal_id = graph.node(*next_index).unwrap();
-
-                if *level == 0 {
-                    // Skip if it's going from to an outer (aside from warping inward)
-                    if *portal_id >= OUTER && *portal_id != ZZ && *level_diff == 0 {
-                        return None;
-                    }
-                } else {
-                    // AA and ZZ are walls on the inner levels.
-                    if *portal_id == AA || *portal_id == ZZ {
-                        return None;
-                    }
+    let mut search = search2::dijkstra(WithCost((aa, 0), 0u32))
+        .optimal_goals(|WithCost((index, _), _)| *index == zz, |s| s.1)
+        .prune_by_best();
+
+    (&mut search).run(|search, WithCost((index, level), cost)| {
+        for (next_index, (move_cost, level_diff)) in graph.edges(*index).unwrap() {
+            let portal_id = graph.node(*next_index).unwrap();
+
+            if *level == 0 {
+                // Skip if it's going from to an outer (aside from warping inward)
+                if *portal_id >= OUTER && *portal_id != ZZ && *level_diff == 0 {
+                    continue;
                 }
+            } else {
+                // AA and ZZ are walls on the inner levels.
+                if *portal_id == AA || *portal_id == ZZ {
+                    continue;
+                }
+            }
 
-                Some((*move_cost, 0, (*next_index, *level + level_diff)))
-            }).collect())
+            search.add_step(WithCost((*next_index, *level + level_diff), cost + *move_cost));
         }
-    }).unwrap().0
+
+        None::<()>
+    }).for_each(drop);
+
+    *search.best_cost().unwrap()
 }
 
-fn parse(data: &[u8]) -> Graph<u16, (u32, i32)> {
+fn parse(data: &[u8]) -> KeyedGraph<u16, (u32, i32), u16> {
     let raw_grid = VecGrid::parse_lines(data, b'\n').unwrap();
     let mut data = Vec::with_capacity(raw_grid.width() * raw_grid.height());
-    let mut graph = Graph::new();
+    let mut graph = KeyedGraph::new(|id: &u16| *id);
     let mut positions = Vec::with_capacity(32);
 
     for (Point(x, y), v) in raw_grid.cells() {
@@ -89,8 +97,10 @@ fn parse(data: &[u8]) -> Graph<u16, (u32, i32)> {
                     let is_outer = pos.0 == 2 || pos.1 == 2 || pos.0 == raw_grid.width() - 3 || pos.1 == raw_grid.height() - 3;
                     let portal_id = portal_id(is_outer, [*v, v2]).unwrap();
 
+                    // The positions are indexed like the nodes, so there can only be one of each.
+                    assert!(graph.find(&portal_id).is_none(), "portal {} appears twice", format_pid(portal_id));
                     positions.push(pos);
-                    graph.insert(portal_id);
+                    graph.find_or_insert(portal_id);
                 }
 
                 data.push(MazeCell::Wall);
@@ -115,31 +125,28 @@ fn parse(data: &[u8]) -> Graph<u16, (u32, i32)> {
         }
     });
 
-    let mut bfs = BFS::new();
     for i in 0..graph.len() {
         let my_id = *graph.node(i).unwrap();
         let pos = positions[i];
 
-        bfs.run(pos, |p| {
-            let valid_neighbours = p.cardinals().into_iter().filter(|p| {
-                if let Some(c) = grid.get(p) {
-                    *c != MazeCell::Wall
-                } else {
-                    false
-                }
-            }).collect();
+        let mut search = search2::bfs(WithCost(pos, 0u32))
+            .collect_goals(|WithCost(p, _)| matches!(grid.get(p), Some(MazeCell::Portal(_))));
 
-            match grid.get(p).unwrap() {
-                MazeCell::Wall => BFSResult::DeadEnd,
-                MazeCell::Ground => BFSResult::Continue(valid_neighbours),
-                MazeCell::Portal(pid) => BFSResult::Found(*pid, valid_neighbours),
+        (&mut search).run(|search, WithCost(p, steps)| {
+            for next in p.cardinals() {
+                if matches!(grid.get(&next), Some(c) if *c != MazeCell::Wall) {
+                    search.add_step(WithCost(next, steps + 1));
+                }
             }
-        });
 
-        for (other_id, steps) in bfs.found_goals().iter() {
-            if *other_id == my_id {
-                continue;
-            }
+            None::<()>
+        }).for_each(drop);
+
+        for WithCost(p, steps) in search.goals().iter() {
+            let other_id = match grid.get(p) {
+                Some(MazeCell::Portal(pid)) if *pid != my_id => pid,
+                _ => continue,
+            };
 
             let j = graph.find(other_id).unwrap();
             graph.connect(i, j, (*steps, 0));
@@ -163,7 +170,6 @@ enum MazeCell {
     Wall,
 }
 
-#[allow(unused)]
 fn format_pid(portal_id: u16) -> String {
     let l1 = (portal_id / 26) % 26;
     let l2 = portal_id % 26;
@@ -194,3 +200,38 @@ fn portal_id(outer: bool, name: [u8; 2]) -> Option<u16> {
 const OUTER: u16 = 26 * 26;
 const AA: u16 = OUTER + 0;
 const ZZ: u16 = OUTER + ((26 * 26) - 1);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &'static [u8] = b"         A           
+         A           
+  #######.#########  
+  #######.........#  
+  #######.#######.#  
+  #######.#######.#  
+  #######.#######.#  
+  #####  B    ###.#  
+BC...##  C    ###.#  
+  ##.##       ###.#  
+  ##...DE  F  ###.#  
+  #####    G  ###.#  
+  #########.#####.#  
+DE..#######...###.#  
+  #.#########.###.#  
+FG..#########.....#  
+  ###########.#####  
+             Z       
+             Z       \n";
+
+    #[test]
+    fn p1_works_on_example() {
+        assert_eq!(part1(&parse(SAMPLE)), 23);
+    }
+
+    #[test]
+    fn p2_works_on_example() {
+        assert_eq!(part2(&parse(SAMPLE)), 26);
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 use std::fmt::{Display, Formatter};
 use std::mem;
-use rustc_hash::FxHashSet;
+use common::cycle::CycleDetector;
 
 common::day!(parse, part1, part2, 100000, 1000, 50);
 
@@ -118,16 +118,16 @@ impl Bugs {
 }
 
 fn part1(input: &Bugs) -> u32 {
-    let mut seen = FxHashSet::default();
-    seen.insert(*input);
+    let mut detector = CycleDetector::new();
 
+    // The biodiversity rating of a layout is its bits, so it's the metric of each minute.
     let mut current = *input;
-    loop {
+    while detector.push(current, current.0 as i64).is_none() {
         current = current.next_minute();
-        if !seen.insert(current) {
-            return current.0;
-        }
     }
+
+    let cycle = detector.cycle().unwrap();
+    detector.metric_at(cycle.start).unwrap() as u32
 }
 
 fn part2(input: &Bugs) -> u32 {
@@ -1,7 +1,7 @@
 use std::cmp::max;
 use common::aoc::{Day, ResultPair};
 use common::geo::Point;
-use common::grid2::{FixedGrid, GetterMutGrid, VecGrid};
+use common::grid2::{FixedGrid, GetterMutGrid, RayGrid, VecGrid};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let input = day.run_parse(1000, || parse(input));
@@ -22,6 +22,8 @@ fn parse(data: &[u8]) -> VecGrid<u8> {
 }
 
 fn both_parts<G>(input: &G) -> ResultPair<usize, u32> where G: GetterMutGrid<u8> + FixedGrid {
+    const DIRECTIONS: [Point<isize>; 4] = [Point(-1, 0), Point(1, 0), Point(0, -1), Point(0, 1)];
+
     let mut visible_count = 0;
     let mut best_scenic_score = 0;
     for y in 1..input.height() - 1 {
@@ -29,56 +31,24 @@ fn both_parts<G>(input: &G) -> ResultPair<usize, u32> where G: GetterMutGrid<u8>
             let current = Point(x, y);
             let current_value = input.get(&current).unwrap();
 
-            let mut up = 0;
-            let mut right = 0;
-            let mut down = 0;
-            let mut left = 0;
-            let mut hidden = 0u8;
-
-            for x in (0..x).rev() {
-                left += 1;
-
-                let neighbor_value = input.get(&Point(x, y)).unwrap();
-                if *neighbor_value >= *current_value {
-                    hidden |= 0b0001;
-                    break;
-                }
-            }
-            for x in x + 1..input.width() {
-                right += 1;
-
-                let neighbor_value = input.get(&Point(x, y)).unwrap();
-                if *neighbor_value >= *current_value {
-                    hidden |= 0b0010;
-                    break;
-                }
-            }
-            for y in (0..y).rev() {
-                up += 1;
-
-                let neighbor_value = input.get(&Point(x, y)).unwrap();
-                if *neighbor_value >= *current_value {
-                    hidden |= 0b0100;
-                    break;
-                }
-            }
-            for y in y + 1..input.height() {
-                down += 1;
+            let mut scenic_score = 1;
+            let mut visible = false;
+            for dir in DIRECTIONS.iter() {
+                let (distance, blocker) = input.first_visible(&current, dir, |v| *v >= *current_value);
 
-                let neighbor_value = input.get(&Point(x, y)).unwrap();
-                if *neighbor_value >= *current_value {
-                    hidden |= 0b1000;
-                    break;
+                scenic_score *= distance as u32;
+                if blocker.is_none() {
+                    visible = true;
                 }
             }
 
-            if hidden != 0b1111 {
+            if visible {
                 #[cfg(test)] println!("Visible position {},{}", x, y);
                 visible_count += 1;
             }
 
-            #[cfg(test)] println!("Scenic score {},{}: {}", x, y, up * down * left * right);
-            best_scenic_score = max(best_scenic_score, up * down * left * right);
+            #[cfg(test)] println!("Scenic score {},{}: {}", x, y, scenic_score);
+            best_scenic_score = max(best_scenic_score, scenic_score);
         }
     }
 
@@ -1,7 +1,10 @@
 use common::aoc::Day;
 use common::geo::Point;
 use common::grid2::{FixedGrid, GetterMutGrid, VecGrid};
-use common::search::{BFS, BFSResult};
+use common::parse3;
+use common::parse3::Parser;
+use common::search2;
+use common::search2::{Search, WithCost};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let (input, start_point, end_point) = day.run_parse(1000, || parse(input));
@@ -11,73 +14,54 @@ pub fn main(day: &mut Day, input: &[u8]) {
 }
 
 fn parse(data: &[u8]) -> (VecGrid<u8>, Point<usize>, Point<usize>) {
-    let width = data.iter().take_while(|b| **b != b'\n').count();
-    let mut filtered_data = Vec::<u8>::with_capacity(data.len());
-    let mut start_point = Point(0, 0);
-    let mut end_point = Point(0, 0);
+    let (grid, markers) = parse3::try_grid(|b| match b {
+        b'S' => Some(0),
+        b'E' => Some(26),
+        b'a'..=b'z' => Some(b - b'a'),
+        _ => None,
+    }).with_markers(b"SE").parse(data).unwrap();
 
-    for v in data {
-        match *v {
-            b'a'..=b'z' => filtered_data.push(*v - b'a'),
-            b'S' => {
-                start_point = Point(filtered_data.len() % width, filtered_data.len() / width);
-                filtered_data.push(0);
-            }
-            b'E' => {
-                end_point = Point(filtered_data.len() % width, filtered_data.len() / width);
-                filtered_data.push(26);
-            }
-            _ => {}
-        }
-    }
+    let start_point = markers.iter().find(|(b, _)| *b == b'S').unwrap().1;
+    let end_point = markers.iter().find(|(b, _)| *b == b'E').unwrap().1;
 
-    (VecGrid::new_from(width, filtered_data), start_point, end_point)
+    (grid, start_point, end_point)
 }
 
 fn part1<G: FixedGrid + GetterMutGrid<u8>>(input: &G, start_point: &Point<usize>, end_point: &Point<usize>) -> u32 {
-    let mut bfs: BFS<Point<usize>, u8> = BFS::new();
+    let mut search = search2::bidirectional_bfs(*start_point, *end_point);
 
-    let (_, distance) = bfs.run(*start_point, |p| {
-        if let Some(elevation) = input.get(p) {
-            if p == end_point {
-                return BFSResult::Success;
-            } else {
-                BFSResult::Continue(p.cardinals().iter().filter(|p| {
-                    if let Some(next_elevation) = input.get(*p) {
-                        *next_elevation <= *elevation + 1
-                    } else {
-                        false
-                    }
-                }).copied().collect())
-            }
-        } else {
-            BFSResult::DeadEnd
-        }
-    }).unwrap();
+    let meeting = search.run(
+        |p, next| {
+            let elevation = *input.get(p).unwrap();
+            next.extend(p.cardinals().into_iter()
+                .filter(|p| input.get(p).map(|e| *e <= elevation + 1).unwrap_or(false)));
+        },
+        |p, next| {
+            let elevation = *input.get(p).unwrap();
+            next.extend(p.cardinals().into_iter()
+                .filter(|p| input.get(p).map(|e| elevation <= *e + 1).unwrap_or(false)));
+        },
+    ).unwrap();
 
-    distance
+    meeting.cost as u32
 }
 
 fn part2<G: FixedGrid + GetterMutGrid<u8>>(input: &G, end_point: &Point<usize>) -> u32 {
-    let mut bfs: BFS<Point<usize>, u8> = BFS::new();
+    search2::bfs(WithCost(*end_point, 0u32))
+        .run(|search, WithCost(p, distance)| {
+            let elevation = *input.get(p).unwrap();
+            if elevation == 0 {
+                return Some(*distance);
+            }
 
-    let (_, distance) = bfs.run(*end_point, |p| {
-        if let Some(elevation) = input.get(p) {
-            if *elevation == 0 {
-                return BFSResult::Success;
-            } else {
-                BFSResult::Continue(p.cardinals().iter().filter(|p| {
-                    if let Some(next_elevation) = input.get(*p) {
-                        *elevation <= *next_elevation + 1
-                    } else {
-                        false
-                    }
-                }).copied().collect())
+            for next in p.cardinals() {
+                if input.get(&next).map(|e| elevation <= *e + 1).unwrap_or(false) {
+                    search.add_step(WithCost(next, distance + 1));
+                }
             }
-        } else {
-            BFSResult::DeadEnd
-        }
-    }).unwrap();
 
-    distance
+            None
+        })
+        .next()
+        .unwrap()
 }
@@ -1,10 +1,10 @@
 use std::cmp::{max, min};
-use smallvec::smallvec;
 use common::aoc::{Day, ResultAndCarry};
 use common::geo::Point;
-use common::grid2::{FixedGrid, GetterMutGrid, render_grid, VecGrid};
+use common::grid2::{FixedGrid, GetterMutGrid, VecGrid};
 use common::parse3::{choice, Parser, point, unsigned_int};
-use common::search::{BFS, BFSResult};
+use common::search2;
+use common::search2::Search;
 
 const BLANK: u8 = 0b00;
 const WALL: u8 = 0b10;
@@ -143,46 +143,53 @@ fn simulate<G>(grid: &mut G, source: &Point<usize>, height: usize) -> (Point<usi
 }
 
 fn part2_bfs<G>(grid: &G, source_x: usize) -> usize where G: GetterMutGrid<u8> + FixedGrid {
-    let mut bfs: BFS<Point<usize>, ()> = BFS::new();
-
-    bfs.run(Point(source_x, 0), |pos| {
-        if pos.1 == grid.height() {
-            return BFSResult::DeadEnd;
+    let is_sand = |pos: &Point<usize>| pos.1 < grid.height() && *grid.get(pos).unwrap() == BLANK;
+    let mut search = search2::bfs(Point(source_x, 0)).collect_goals(is_sand);
+
+    (&mut search).run(|search, pos| {
+        if is_sand(pos) {
+            search.add_step(Point(pos.0, pos.1 + 1));
+            search.add_step(Point(pos.0 - 1, pos.1 + 1));
+            search.add_step(Point(pos.0 + 1, pos.1 + 1));
         }
 
-        if *grid.get(pos).unwrap() == BLANK {
-            BFSResult::Found((), smallvec![
-                Point(pos.0, pos.1 + 1),
-                Point(pos.0 - 1, pos.1 + 1),
-                Point(pos.0 + 1, pos.1 + 1),
-            ])
-        } else {
-            BFSResult::DeadEnd
-        }
-    });
+        None::<()>
+    }).for_each(drop);
 
-    bfs.found_goals().len()
-}
-
-#[allow(dead_code)]
-fn render_sand_grid<G>(grid: &G) -> String where G: GetterMutGrid<u8> + FixedGrid {
-    render_grid::<G, _, _>(&grid, |c| match *c {
-        WALL => ('#', None),
-        SAND => ('o', None),
-        _ => ('·', None),
-    })
+    search.goals().len()
 }
 
 #[cfg(test)]
 mod tests {
+    use common::grid2::SubGrid;
+    use common::legend::{assert_grid_eq, Legend, SymbolTable};
     use super::*;
 
     const P1_EXAMPLE: &[u8] = b"498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9\n";
 
+    fn legend() -> SymbolTable<u8> {
+        SymbolTable::new(&[(b'.', BLANK), (b'#', WALL), (b'o', SAND)])
+    }
+
     #[test]
     fn p1_works_on_example() {
         let (grid, source_x) = parse(&P1_EXAMPLE);
-        assert_eq!(part1(grid, source_x).0, 24);
+        let ResultAndCarry(sand_count, grid) = part1(grid, source_x);
+        assert_eq!(sand_count, 24);
+
+        let window = SubGrid::new(&grid, Point(source_x - 6, 0), 10, 10);
+        assert_grid_eq(&legend().render_grid(&window), "
+..........
+..........
+......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########.
+");
     }
 
     #[test]
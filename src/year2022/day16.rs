@@ -1,11 +1,11 @@
 use std::cmp::{max, Ordering};
 use std::hash::{Hash, Hasher};
 use std::ops::Add;
-use smallvec::SmallVec;
 use common::aoc::Day;
-use common::graph::Graph;
+use common::graph::{Graph, KeyedGraph};
 use common::parse3::{n_bytes_array, Parser, unsigned_int};
-use common::search::{BFS, BFSResult, Dijkstra, DijkstraResult};
+use common::search2;
+use common::search2::{Search, WithCost};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let graph: Graph<Valve, i8> = day.run_parse(1000, || parse(input));
@@ -20,7 +20,7 @@ pub fn main(day: &mut Day, input: &[u8]) {
 
 fn parse(data: &[u8]) -> Graph<Valve, i8> {
     // Parse and build unweighted graph
-    let graph_unweighted: Graph<Valve, ()> = b"Valve ".and_instead(n_bytes_array::<2>())
+    let graph_unweighted: KeyedGraph<Valve, (), [u8; 2]> = b"Valve ".and_instead(n_bytes_array::<2>())
         .and_discard(b" has flow rate=")
         .and(unsigned_int::<u64>())
         .and_discard(b"; tunnels lead to valves ".or(b"; tunnel leads to valve "))
@@ -28,7 +28,7 @@ fn parse(data: &[u8]) -> Graph<Valve, i8> {
         .skip(b'\n')
         .repeat_fold_mut(
             || {
-                let mut graph = Graph::new();
+                let mut graph = KeyedGraph::new(|v: &Valve| v.name);
                 graph.insert(Valve {
                     name: [b'A', b'A'],
                     flow_rate: 0,
@@ -36,19 +36,11 @@ fn parse(data: &[u8]) -> Graph<Valve, i8> {
 
                 graph
             },
-            |graph: &mut Graph<Valve, ()>, ((name, flow_rate), list): (([u8; 2], u64), Vec<[u8; 2]>)| {
-                let current_index = graph.find_by(|v| v.name == name)
-                    .and_then(|i| {
-                        graph.node_mut(i).unwrap().flow_rate = flow_rate;
-                        Some(i)
-                    })
-                    .or_else(|| Some(graph.insert(Valve { name, flow_rate })))
-                    .unwrap();
+            |graph: &mut KeyedGraph<Valve, (), [u8; 2]>, ((name, flow_rate), list): (([u8; 2], u64), Vec<[u8; 2]>)| {
+                let current_index = graph.insert(Valve { name, flow_rate });
 
                 for name in list {
-                    let next_index = graph.find_by(|v| v.name == name)
-                        .or_else(|| Some(graph.insert(Valve { name, flow_rate: 0 })))
-                        .unwrap();
+                    let next_index = graph.find_or_insert(Valve { name, flow_rate: 0 });
 
                     graph.connect_mutual(current_index, next_index, ());
                 }
@@ -56,45 +48,38 @@ fn parse(data: &[u8]) -> Graph<Valve, i8> {
         .parse(data).unwrap();
 
     // Build a weighted graph
-    let mut graph_weighted: Graph<Valve, i8> = Graph::new();
-    let mut bfs: BFS<usize, usize> = BFS::new();
+    let mut graph_weighted = KeyedGraph::new(|v: &Valve| v.name);
     graph_weighted.insert(Valve { flow_rate: 0, name: [b'A', b'A'] });
     for i in 0..graph_unweighted.len() {
         let valve = graph_unweighted.node(i).unwrap();
         if valve.flow_rate > 0 || i == 0 {
-            bfs.run(i, |index| {
-                let valve = graph_unweighted.node(*index).unwrap();
-                let next = graph_unweighted.edges(*index).unwrap()
-                    .map(|(i, _)| *i)
-                    .collect();
+            let mut search = search2::bfs(WithCost(i, 0u32)).collect_goals(|WithCost(index, _)| {
+                *index != i && (graph_unweighted.node(*index).unwrap().flow_rate > 0 || *index == 0)
+            });
 
-                if *index != i && (valve.flow_rate > 0 || *index == 0) {
-                    return BFSResult::Found(*index, next);
-                } else {
-                    BFSResult::Continue(next)
+            (&mut search).run(|search, WithCost(index, steps)| {
+                for (next_index, _) in graph_unweighted.edges(*index).unwrap() {
+                    search.add_step(WithCost(*next_index, steps + 1));
                 }
-            });
 
-            let current_index = graph_weighted.find_by(|v| v.name == valve.name)
-                .or_else(|| Some(graph_weighted.insert(valve.clone())))
-                .unwrap();
+                None::<()>
+            }).for_each(drop);
+
+            let current_index = graph_weighted.find_or_insert(valve.clone());
 
-            for (index, steps) in bfs.found_goals().iter() {
+            for WithCost(index, steps) in search.goals().iter() {
                 let valve = graph_unweighted.node(*index).unwrap();
-                let new_index = graph_weighted.find_by(|v| v.name == valve.name)
-                    .or_else(|| Some(graph_weighted.insert(valve.clone())))
-                    .unwrap();
+                let new_index = graph_weighted.find_or_insert(valve.clone());
 
                 graph_weighted.connect(current_index, new_index, *steps as i8 + 1)
             }
         }
     }
 
-    graph_weighted
+    graph_weighted.into_graph()
 }
 
 fn part1(graph: &Graph<Valve, i8>) -> u64 {
-    let mut dijkstra: Dijkstra<State, Pressure> = Dijkstra::new(false, false);
     let initial_state = State {
         minutes: 30,
         opened: 0,
@@ -102,68 +87,65 @@ fn part1(graph: &Graph<Valve, i8>) -> u64 {
     };
     let all_valves = (1 << graph.len()) - 1;
 
-    let (pressure, _) = dijkstra.run(initial_state, Pressure(0), |state: &State| {
-        // Time's up
-        if state.minutes <= 0 || state.opened == all_valves {
-            return DijkstraResult::Success;
-        }
-
-        let mut next_steps = SmallVec::new();
-        let valve = graph.node(state.index).unwrap();
-        let (state, new_pressure) = state.with_open(valve.flow_rate);
-
-        for (next_index, min) in graph.edges(state.index).unwrap() {
-            if state.has_opened(*next_index) {
-                continue;
+    // Pressure is ordered in reverse, so dijkstra tries the most pressure first.
+    search2::dijkstra(WithCost(initial_state, Pressure(0)))
+        .run(|search, WithCost(state, pressure)| {
+            // Time's up
+            if state.minutes <= 0 || state.opened == all_valves {
+                return Some(pressure.0);
             }
 
-            next_steps.push((new_pressure, Pressure(0), state.with_visit(
-                *next_index, *min,
-            )));
-        }
+            let valve = graph.node(state.index).unwrap();
+            let (state, new_pressure) = state.with_open(valve.flow_rate);
 
-        DijkstraResult::Continue(next_steps)
-    }).unwrap();
+            for (next_index, min) in graph.edges(state.index).unwrap() {
+                if !state.has_opened(*next_index) {
+                    search.add_step(WithCost(state.with_visit(*next_index, *min), *pressure + new_pressure));
+                }
+            }
 
-    pressure.0
+            None
+        })
+        .max()
+        .unwrap()
 }
 
 fn part1_dfs(graph: &Graph<Valve, i8>) -> u64 {
     let all_valves = (1 << graph.len()) - 1;
-    let mut stack = Vec::with_capacity(64);
-    let mut best = 0;
-    let mut seen = vec![0u64; 1 << 20];
-    stack.push((State { minutes: 30, opened: 0, index: 0 }, 0u64));
-
-    while let Some((state, pressure)) = stack.pop() {
-        if seen_before(&mut seen, state.pack(), pressure) {
-            continue;
-        }
-
-        if state.minutes <= 0 || state.opened == all_valves {
-            if pressure > best {
-                best = pressure;
-            }
-            continue;
-        }
-
-        let valve = graph.node(state.index).unwrap();
-        let (state, new_pressure) = state.with_open(valve.flow_rate);
-        for (next_index, min) in graph.edges(state.index).unwrap() {
-            if state.has_opened(*next_index) {
-                continue;
-            }
+    let opened = |(state, pressure): &(State, u64)| {
+        let (state, new_pressure) = state.with_open(graph.node(state.index).unwrap().flow_rate);
+        (state, pressure + new_pressure.0)
+    };
 
-            stack.push((state.with_visit(*next_index, *min), pressure + new_pressure.0))
-        }
-    }
+    search2::maximize((State { minutes: 30, opened: 0, index: 0 }, 0u64))
+        .memo(|(state, pressure)| ((state.pack(), state.minutes), *pressure))
+        .run(
+            |s, next| {
+                let (state, pressure) = opened(s);
+                if state.minutes <= 0 || state.opened == all_valves {
+                    return;
+                }
 
-    best
+                for (next_index, min) in graph.edges(state.index).unwrap() {
+                    if !state.has_opened(*next_index) {
+                        next.push((state.with_visit(*next_index, *min), pressure));
+                    }
+                }
+            },
+            |s| Some(opened(s).1),
+            |s| {
+                let (state, pressure) = opened(s);
+                pressure + graph.edges(state.index).unwrap()
+                    .filter(|(index, _)| !state.has_opened(*index))
+                    .map(|(index, min)| graph.node(*index).unwrap().flow_rate * max(state.minutes - *min, 0) as u64)
+                    .sum::<u64>()
+            },
+        )
+        .unwrap()
 }
 
 #[allow(dead_code)]
 fn part2(graph: &Graph<Valve, i8>) -> u64 {
-    let mut dijkstra: Dijkstra<State2, Pressure> = Dijkstra::new(false, false);
     let initial_state = State2 {
         minutes: (26, 26),
         opened: 0,
@@ -171,122 +153,96 @@ fn part2(graph: &Graph<Valve, i8>) -> u64 {
     };
     let all_valves = (1 << graph.len()) - 1;
 
-    let (pressure, _) = dijkstra.run(initial_state, Pressure(0), |state: &State2| {
-        // Time's up
-        if (state.minutes.0 <= 0 && state.minutes.1 <= 0) || state.opened == all_valves {
-            return DijkstraResult::Success;
-        }
+    search2::dijkstra(WithCost(initial_state, Pressure(0)))
+        .run(|search, WithCost(state, pressure)| {
+            // Time's up
+            if (state.minutes.0 <= 0 && state.minutes.1 <= 0) || state.opened == all_valves {
+                return Some(pressure.0);
+            }
 
-        let mut next_steps = SmallVec::new();
-        let elf_valve = graph.node(state.index.0).unwrap();
-        let elephant_valve = graph.node(state.index.1).unwrap();
-        let (next_state, new_pressure) = state.with_open(elf_valve, elephant_valve);
+            let elf_valve = graph.node(state.index.0).unwrap();
+            let elephant_valve = graph.node(state.index.1).unwrap();
+            let (next_state, new_pressure) = state.with_open(elf_valve, elephant_valve);
+            let pressure = *pressure + new_pressure;
 
-        if state.minutes.0 > state.minutes.1 {
-            for (elf_index, dist) in graph.edges(state.index.0).unwrap() {
-                if next_state.has_opened(*elf_index) {
-                    continue;
+            if state.minutes.0 > state.minutes.1 {
+                for (elf_index, dist) in graph.edges(state.index.0).unwrap() {
+                    if !next_state.has_opened(*elf_index) {
+                        search.add_step(WithCost(next_state.with_elf_visit(*elf_index, *dist), pressure));
+                    }
                 }
-
-                let next_state = next_state.with_elf_visit(*elf_index, *dist);
-                next_steps.push((new_pressure, Pressure(0), next_state));
-            }
-        } else {
-            for (elephant_index, dist) in graph.edges(state.index.1).unwrap() {
-                if next_state.has_opened(*elephant_index) {
-                    continue;
+            } else {
+                for (elephant_index, dist) in graph.edges(state.index.1).unwrap() {
+                    if !next_state.has_opened(*elephant_index) {
+                        search.add_step(WithCost(next_state.with_elephant_visit(*elephant_index, *dist), pressure));
+                    }
                 }
-
-                let next_state = next_state.with_elephant_visit(*elephant_index, *dist);
-                next_steps.push((new_pressure, Pressure(0), next_state));
             }
-        }
 
-        DijkstraResult::Continue(next_steps)
-    }).unwrap();
-
-    pressure.0
+            None
+        })
+        .max()
+        .unwrap()
 }
 
 fn part2_dfs(graph: &Graph<Valve, i8>) -> u64 {
     let all_valves = (1 << graph.len()) - 1;
-    let mut stack = Vec::with_capacity(64);
-    let mut best = 0;
-    let mut seen = vec![0u64; 1 << 24];
-
-    stack.push((State2 { minutes: (26, 26), opened: 1, index: (0, 0) }, 0u64));
-
-    while let Some((state, pressure)) = stack.pop() {
-        if seen_before(&mut seen, state.pack(), pressure) {
-            continue;
-        }
-
-        if (state.minutes.0 <= 0 && state.minutes.1 <= 0) || state.opened == all_valves {
-            if pressure > best {
-                best = pressure;
-            }
-
-            continue;
-        }
-
+    let opened = |(state, pressure): &(State2, u64)| {
         let elf_valve = graph.node(state.index.0).unwrap();
         let elephant_valve = graph.node(state.index.1).unwrap();
-        let (next_state, new_pressure) = state.with_open(elf_valve, elephant_valve);
+        let (state, new_pressure) = state.with_open(elf_valve, elephant_valve);
+        (state, pressure + new_pressure.0)
+    };
 
-        if state.minutes.0 < 15 || state.minutes.1 < 15 {
-            let mut total_potential = pressure + new_pressure.0;
-            for (index, elf_dist) in graph.edges(state.index.0).unwrap() {
-                if next_state.has_opened(*index) {
-                    continue;
+    search2::par_maximize((State2 { minutes: (26, 26), opened: 1, index: (0, 0) }, 0u64))
+        .memo(|(state, pressure)| ((state.opened, minmax(((state.index.0, state.minutes.0), (state.index.1, state.minutes.1)))), *pressure))
+        .run(
+            |s, next| {
+                let (next_state, pressure) = opened(s);
+                let state = &s.0;
+                if (state.minutes.0 <= 0 && state.minutes.1 <= 0) || state.opened == all_valves {
+                    return;
                 }
 
-                let elephant_dist = graph.edge(state.index.1, *index)
-                    .unwrap_or(&0);
-                let best_minute = max(max(
-                    state.minutes.0 - *elf_dist,
-                    state.minutes.1 - *elephant_dist,
-                ), 0);
-
-                let next_valve = graph.node(*index).unwrap();
-                total_potential += next_valve.flow_rate * (best_minute as u64)
-            }
-
-            if total_potential <= best {
-                continue;
-            }
-        }
-
-        if state.minutes.0 > state.minutes.1 {
-            for (elf_index, dist) in graph.edges(state.index.0).unwrap() {
-                if next_state.has_opened(*elf_index) {
-                    continue;
+                if state.minutes.0 > state.minutes.1 {
+                    for (elf_index, dist) in graph.edges(state.index.0).unwrap() {
+                        if !next_state.has_opened(*elf_index) {
+                            next.push((next_state.with_elf_visit(*elf_index, *dist), pressure));
+                        }
+                    }
+                } else {
+                    for (elephant_index, dist) in graph.edges(state.index.1).unwrap() {
+                        if !next_state.has_opened(*elephant_index) {
+                            next.push((next_state.with_elephant_visit(*elephant_index, *dist), pressure));
+                        }
+                    }
                 }
-
-                let next_state = next_state.with_elf_visit(*elf_index, *dist);
-                stack.push((next_state, pressure + new_pressure.0));
-            }
-        } else {
-            for (elephant_index, dist) in graph.edges(state.index.1).unwrap() {
-                if next_state.has_opened(*elephant_index) {
-                    continue;
+            },
+            |s| Some(opened(s).1),
+            |s| {
+                let (next_state, pressure) = opened(s);
+                let state = &s.0;
+                let mut total_potential = pressure;
+                for (index, elf_dist) in graph.edges(state.index.0).unwrap() {
+                    if next_state.has_opened(*index) {
+                        continue;
+                    }
+
+                    let elephant_dist = graph.edge(state.index.1, *index)
+                        .unwrap_or(&0);
+                    let best_minute = max(max(
+                        state.minutes.0 - *elf_dist,
+                        state.minutes.1 - *elephant_dist,
+                    ), 0);
+
+                    let next_valve = graph.node(*index).unwrap();
+                    total_potential += next_valve.flow_rate * (best_minute as u64)
                 }
 
-                let next_state = next_state.with_elephant_visit(*elephant_index, *dist);
-                stack.push((next_state, pressure + new_pressure.0));
-            }
-        }
-    }
-
-    best
-}
-
-fn seen_before(seen: &mut Vec<u64>, seen_index: usize, pressure: u64) -> bool {
-    if seen[seen_index] != 0 && seen[seen_index] > pressure {
-        return true;
-    }
-    seen[seen_index] = pressure;
-
-    return false;
+                total_potential
+            },
+        )
+        .unwrap()
 }
 
 #[derive(Clone, Debug)]
@@ -376,11 +332,6 @@ impl State2 {
         (self.opened & 1 << index) != 0
     }
 
-    fn pack(&self) -> usize {
-        let (si1, si2) = minmax(self.index);
-        self.opened as usize | (si1 << 16) | (si2 << 20)
-    }
-
     fn with_open(&self, elf_valve: &Valve, elephant_valve: &Valve) -> (Self, Pressure) {
         let mut s = *self;
 
@@ -480,12 +431,12 @@ mod tests {
         assert_eq!(jj.flow_rate, 21);
     }
 
-    #[test] #[ignore]
+    #[test]
     fn p1_works_on_example() {
         assert_eq!(part1_dfs(&parse(P1_EXAMPLE)), 1651);
     }
 
-    #[test] #[ignore]
+    #[test]
     fn p2_works_on_example() {
         assert_eq!(part2_dfs(&parse(P1_EXAMPLE)), 1707);
     }
@@ -1,5 +1,6 @@
-use arrayvec::ArrayVec;
+use std::cell::Cell;
 use common::aoc::Day;
+use common::cycle::{CycleDetector, hash_state};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let input = day.run_parse(1000, || parse(input));
@@ -35,55 +36,32 @@ fn part1(jets: &[i64]) -> i64 {
 }
 
 fn part2(jets: &[i64]) -> i64 {
+    const ROCKS: usize = 1000000000000;
+    const WINDOW: usize = 64;
+
     let mut top_y = 0;
     let mut tower = Tower::with_capacity(4096);
-    let mut jets = jets.iter().copied().cycle();
-
-    let mut border: Vec<u8> = Vec::new();
-    let mut border_indices: ArrayVec<usize, 4> = ArrayVec::new();
-    let mut border_ys: ArrayVec<i64, 4> = ArrayVec::new();
-    let mut offset = 0usize;
-    let mut height_offset = 0i64;
+    let jet_count = Cell::new(0usize);
+    let mut jets_iter = jets.iter().copied().cycle().inspect(|_| jet_count.set(jet_count.get() + 1));
+    let mut detector = CycleDetector::new();
 
     for (index, template) in ROCK_TEMPLATES.iter().cycle().enumerate() {
-        let rock = Rock::simulate_fall(top_y, &mut jets, &tower, &template).unwrap();
+        let rock = Rock::simulate_fall(top_y, &mut jets_iter, &tower, &template).unwrap();
         tower.place_rock(&rock);
+        if rock.y < top_y { top_y = rock.y; }
 
-        if rock.y < top_y {
-            top_y = rock.y;
-
-            if top_y < -2000 && border.is_empty() {
-                let i = tower.get_y(-1500);
-                border = Vec::from(&tower.data[i..i + 8]);
-            }
-
-            if top_y < -2500 && !border_indices.is_full() {
-                let i = tower.get_y(top_y);
-                if &tower.data[i - 128..i - 120] == &border {
-                    border_indices.push(index);
-                    border_ys.push(top_y);
-
-                    if border_indices.is_full() {
-                        let cycle_len = border_indices[1] - border_indices[0];
-                        let cycle_height =  border_ys[1] - border_ys[0];
-
-                        let cycle_count = ((999999999999 - index) / cycle_len) - 1;
-
-                        offset = cycle_len * cycle_count;
-                        height_offset = cycle_height * (cycle_count as i64);
-                    }
-                }
-            }
-        }
+        let top = tower.get_y(top_y);
+        let window = &tower.data[top.saturating_sub(WINDOW - 1)..=top];
+        let key = (index % ROCK_TEMPLATES.len(), jet_count.get() % jets.len(), hash_state(window));
 
-        if index + offset == 999999999999 {
+        if detector.push(key, -top_y).is_some() {
             break;
         }
     }
 
     #[cfg(test)] tower.render();
 
-    -(top_y + height_offset)
+    detector.metric_at(ROCKS - 1).unwrap()
 }
 
 #[derive(Clone)]
@@ -291,4 +269,9 @@ mod tests {
     fn p1_works_on_example() {
         assert_eq!(part1(&parse(P1_EXAMPLE)), 3068);
     }
+
+    #[test]
+    fn p2_works_on_example() {
+        assert_eq!(part2(&parse(P1_EXAMPLE)), 1514285714288);
+    }
 }
\ No newline at end of file
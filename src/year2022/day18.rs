@@ -1,19 +1,37 @@
+use std::cell::Cell;
 use common::aoc::Day;
+use common::flood::Flood;
 use common::constants::{U32_3WINDOWS, U32_BITS};
 use common::geo::{Point, Vertex};
 use common::grid2::{ArrayGrid, FixedGrid, GetterMutGrid};
 use common::parse3::{Parser, unsigned_int};
 use common::search2;
-use common::search2::{Bounded, Search};
+use common::search2::{Bounded, DenseSet, Search, SearchStats, VoxelIndex};
+use common::voxel::{ArrayVoxelGrid, GetterMutVoxelGrid, IterableVoxelGrid, NeighborCountVoxelGrid};
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let (grid, max) = day.run_parse(1000, || parse(input));
+    let voxels = day.run_parse_labeled("Voxels", 1000, || parse_voxels(input));
 
     day.note("Max", max);
 
     day.run(1, "", 10000, || part1(&grid, &max));
-    day.run(2, "BFS", 200, || part2_dfs(&grid, &max));
-    day.run(2, "DFS", 200, || part2_bfs(&grid, &max));
+    day.run(1, "Voxels", 1000, || part1_voxels(&voxels));
+    let (dfs_stats, bfs_stats) = (Cell::new(SearchStats::default()), Cell::new(SearchStats::default()));
+    day.run(2, "BFS", 200, || {
+        let (res, stats) = part2_dfs(&grid, &max);
+        dfs_stats.set(stats);
+        res
+    });
+    day.run(2, "DFS", 200, || {
+        let (res, stats) = part2_bfs(&grid, &max);
+        bfs_stats.set(stats);
+        res
+    });
+    day.run(2, "Flood", 200, || part2_flood(&voxels));
+
+    day.note("DFS Stats", dfs_stats.get());
+    day.note("BFS Stats", bfs_stats.get());
 }
 
 fn parse(data: &[u8]) -> (ArrayGrid<u32, 1024, 32>, Vertex<usize>) {
@@ -32,6 +50,17 @@ fn parse(data: &[u8]) -> (ArrayGrid<u32, 1024, 32>, Vertex<usize>) {
     (grid, max)
 }
 
+fn parse_voxels(data: &[u8]) -> Voxels {
+    let parser = Vertex::comma_separated_parser(unsigned_int::<usize>()).skip(b'\n');
+
+    let mut grid = Voxels::new();
+    for v in parser.iterate(data) {
+        *grid.get_mut(&(v + Vertex(1, 1, 1))).unwrap() = true;
+    }
+
+    grid
+}
+
 fn part1<G: GetterMutGrid<u32> + FixedGrid>(grid: &G, max: &Vertex<usize>) -> u32 {
     let mut surface_area = 0;
     for y in 1..=max.1 {
@@ -58,10 +87,17 @@ fn part1<G: GetterMutGrid<u32> + FixedGrid>(grid: &G, max: &Vertex<usize>) -> u3
     surface_area
 }
 
-fn part2<G: GetterMutGrid<u32>, S: Search<Vertex<usize>>>(grid: &G, max: &Vertex<usize>, search: S) -> usize {
+fn part1_voxels(grid: &Voxels) -> usize {
+    grid.cells()
+        .filter(|(_, v)| **v)
+        .map(|(p, _)| 6 - grid.count_cardinals(&p, &true))
+        .sum()
+}
+
+fn part2<G: GetterMutGrid<u32>, S: Search<Vertex<usize>>>(grid: &G, max: &Vertex<usize>, search: S) -> (usize, SearchStats) {
     let m = *max + Vertex(1, 1, 1);
 
-    search
+    let mut run = search
         .bounded(|s| s.0 > 0 && s.1 > 0 && s.2 > 0 && s.0 <= m.0 && s.1 <= m.1 && s.2 <= m.2)
         .run(|dfs: &mut Bounded<S, _>, s: &Vertex<usize>| {
             match grid.get(&Point(s.0, s.1)) {
@@ -89,18 +125,35 @@ fn part2<G: GetterMutGrid<u32>, S: Search<Vertex<usize>>>(grid: &G, max: &Vertex
                 }
                 None => None,
             }
-        })
-        .sum()
+        });
+
+    (run.by_ref().sum(), run.stats())
 }
 
-fn part2_dfs<G: GetterMutGrid<u32>>(grid: &G, max: &Vertex<usize>) -> usize {
-    part2(grid, max, search2::dfs(Vertex(1, 1, 1)))
+fn part2_dfs<G: GetterMutGrid<u32>>(grid: &G, max: &Vertex<usize>) -> (usize, SearchStats) {
+    part2(grid, max, search2::dfs_with(Vertex(1, 1, 1), DenseSet::new(seen_index(max))))
 }
 
-fn part2_bfs<G: GetterMutGrid<u32>>(grid: &G, max: &Vertex<usize>) -> usize {
-    part2(grid, max, search2::bfs(Vertex(1, 1, 1)))
+fn part2_bfs<G: GetterMutGrid<u32>>(grid: &G, max: &Vertex<usize>) -> (usize, SearchStats) {
+    part2(grid, max, search2::bfs_with(Vertex(1, 1, 1), DenseSet::new(seen_index(max))))
 }
 
+/// The searches stay within `max + 1`, which is all the seen set needs to cover.
+fn seen_index(max: &Vertex<usize>) -> VoxelIndex {
+    VoxelIndex { width: max.0 + 2, height: max.1 + 2, depth: max.2 + 2 }
+}
+
+fn part2_flood(grid: &Voxels) -> usize {
+    let outside = Flood::new(grid).passable(|v| !*v).fill(Vertex(0, 0, 0));
+
+    grid.cells()
+        .filter(|(_, v)| **v)
+        .map(|(p, _)| outside.count_cardinals(&p, &true))
+        .sum()
+}
+
+type Voxels = ArrayVoxelGrid<bool, 32768, 32, 32>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,9 +166,25 @@ mod tests {
         let (grid_simple, max_simple) = parse(P1_SIMPLE_EXAMPLE);
         let (grid, max) = parse(P1_EXAMPLE);
 
-        assert_eq!(part2_dfs(&grid_simple, &max_simple), 10);
-        assert_eq!(part2_dfs(&grid, &max), 58);
-        assert_eq!(part2_bfs(&grid_simple, &max_simple), 10);
-        assert_eq!(part2_dfs(&grid, &max), 58);
+        assert_eq!(part2_dfs(&grid_simple, &max_simple).0, 10);
+        assert_eq!(part2_dfs(&grid, &max).0, 58);
+        assert_eq!(part2_bfs(&grid_simple, &max_simple).0, 10);
+        assert_eq!(part2_dfs(&grid, &max).0, 58);
+
+        // Both fill the same space, in a different order.
+        let (dfs, bfs) = (part2_dfs(&grid, &max).1, part2_bfs(&grid, &max).1);
+        assert_eq!((dfs.pushed, dfs.popped, dfs.bounded), (bfs.pushed, bfs.popped, bfs.bounded));
+        assert!(dfs.bounded > 0 && dfs.duplicates > 0);
+    }
+
+    #[test]
+    fn voxels_work_on_example() {
+        let grid_simple = parse_voxels(P1_SIMPLE_EXAMPLE);
+        let grid = parse_voxels(P1_EXAMPLE);
+
+        assert_eq!(part1_voxels(&grid_simple), 10);
+        assert_eq!(part1_voxels(&grid), 64);
+        assert_eq!(part2_flood(&grid_simple), 10);
+        assert_eq!(part2_flood(&grid), 58);
     }
 }
\ No newline at end of file
@@ -3,7 +3,6 @@ use rayon::prelude::*;
 use common::aoc::Day;
 use common::parse3::{Parser, unsigned_int};
 use common::search2;
-use common::search2::Search;
 
 pub fn main(day: &mut Day, input: &[u8]) {
     let blueprints = day.run_parse(1000, || parse(input));
@@ -40,68 +39,72 @@ struct Blueprint {
 
 impl Blueprint {
     fn maximize(&self, minutes: u8) -> u16 {
-        let mut max_geodes = [0u16; 64];
         let robo_limits = [
             max(max(self.orc, self.cc), max(self.gc[0], self.obc[0])),
             self.obc[1],
             self.gc[1],
         ];
-
-        search2::bfs(State::new())
-            .run(|search: _, s: &State| {
-                if s.minute == minutes {
-                    return Some(s.resources[3]);
+        // The geodes at the end if no more robots are built.
+        let idle_geodes = |s: &State| s.resources[3] + s.robots[3] * (minutes - s.minute) as u16;
+
+        let successors = |s: &State, next_steps: &mut Vec<State>| {
+            if s.minute == minutes {
+                return;
+            }
+
+            let next = s.next();
+            let mut add_next = true;
+            if s.resources[0] >= self.gc[0]
+                && s.resources[2] >= self.gc[1] {
+                next_steps.push(next.clone()
+                    .costing(0, self.gc[0])
+                    .costing(2, self.gc[1])
+                    .having(3));
+
+                add_next = false;
+            }
+            if s.robots[2] < robo_limits[2] && s.resources[0] >= self.obc[0]
+                && s.resources[1] >= self.obc[1] {
+                next_steps.push(next.clone()
+                    .costing(0, self.obc[0])
+                    .costing(1, self.obc[1])
+                    .having(2));
+            }
+
+            if add_next {
+                if s.robots[0] < robo_limits[0] && s.resources[0] >= self.orc {
+                    next_steps.push(next.clone()
+                        .costing(0, self.orc)
+                        .having(0));
                 }
-
-                let geode_score = s.resources[3];
-                let max_geodes = max_geodes.get_mut(s.minute as usize - 1).unwrap();
-                if geode_score >= *max_geodes {
-                    *max_geodes = geode_score;
-                } else {
-                    return None;
+                if s.robots[1] < robo_limits[1] && s.resources[0] >= self.cc {
+                    next_steps.push(next.clone()
+                        .costing(0, self.cc)
+                        .having(1));
                 }
-
-                let next = s.next();
-                let mut add_next = true;
-                if s.resources[0] >= self.gc[0]
-                    && s.resources[2] >= self.gc[1] {
-                    search.add_step(next.clone()
-                        .costing(0, self.gc[0])
-                        .costing(2, self.gc[1])
-                        .having(3));
-
-                    add_next = false;
-                }
-                if s.robots[2] < robo_limits[2] && s.resources[0] >= self.obc[0]
-                    && s.resources[1] >= self.obc[1] {
-                    search.add_step(next.clone()
-                        .costing(0, self.obc[0])
-                        .costing(1, self.obc[1])
-                        .having(2));
-
-                    add_next = false;
-                }
-
-                if add_next {
-                    if s.robots[0] < robo_limits[0] && s.resources[0] >= self.orc {
-                        search.add_step(next.clone()
-                            .costing(0, self.orc)
-                            .having(0));
-                    }
-                    if s.robots[1] < robo_limits[1] && s.resources[0] >= self.cc {
-                        search.add_step(next.clone()
-                            .costing(0, self.cc)
-                            .having(1));
-                    }
-                }
-
-
-
-                    search.add_step(next);
-
-                None
-            })
-            .max().unwrap()
+            }
+
+            next_steps.push(next);
+        };
+
+        // A beam that favors the later robots finds a good start for the bound.
+        let mut beam = search2::beam_search(State::new(), 256).dedupe(|s| *s);
+        beam.run(&successors, |s| (idle_geodes(s), s.robots[2], s.robots[1], s.robots[0]));
+        let (_, seed) = beam.best().unwrap();
+
+        search2::maximize(State::new())
+            .incumbent(idle_geodes(seed), *seed)
+            .memo(|s| ((s.minute, s.robots, [s.resources[0], s.resources[1], s.resources[2]]), s.resources[3]))
+            .run(
+                &successors,
+                |s| Some(idle_geodes(s)),
+                |s| {
+                    // At best, a geode robot is built every minute that's left.
+                    let left = (minutes - s.minute) as u16;
+                    idle_geodes(s) + left * left.saturating_sub(1) / 2
+                },
+            )
+            .unwrap()
     }
 
     fn parser<'i>() -> impl Parser<'i, Blueprint> {
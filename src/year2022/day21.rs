@@ -1,7 +1,7 @@
 use rustc_hash::FxHashMap;
 use common::aoc::Day;
 use common::parse3::{choice, n_bytes_array, Parser, signed_int};
-use common::search::bisect;
+use common::bisect::gallop;
 
 const ROOT: &[u8; 4] = b"root";
 const HUMN: &[u8; 4] = b"humn";
@@ -52,17 +52,16 @@ fn part2(list: &[Monkey], root_index: usize, humn_index: usize) -> i64 {
         let first = list[human_monkey].number_h(list, humn_index, 1000);
         let second = list[human_monkey].number_h(list, humn_index, 2000);
 
-        let mut res = bisect(1125899906842624, 562949953421312, |v| {
-            let ord = list[human_monkey].number_h(list, humn_index, v).cmp(&expected);
-
-            if first > second { ord.reverse() } else { ord }
-        }).unwrap();
-
-        while list[human_monkey].number_h(list, humn_index, res - 1) == expected {
-            res -= 1;
-        }
+        // The number goes one way as the human's number goes up, so the first number that gets
+        // to or past the expected one is the answer.
+        let reached = |v| {
+            let number = list[human_monkey].number_h(list, humn_index, v);
+            if first > second { number <= expected } else { number >= expected }
+        };
 
-        res
+        gallop(0, reached)
+            .filter(|v| list[human_monkey].number_h(list, humn_index, *v) == expected)
+            .unwrap()
     } else {
         panic!("Invalid root monkey")
     }
@@ -1,9 +1,8 @@
-use std::cmp::max;
 use arrayvec::ArrayVec;
-use num::range_step;
 use common::aoc::Day;
+use common::cube_net::CubeNet;
 use common::geo::Point;
-use common::grid2::{FixedGrid, GetterGrid, GetterMutGrid, SubGrid, VecGrid};
+use common::grid2::{FixedGrid, GetterGrid, GetterMutGrid, SubGrid, TileGrid, VecGrid};
 use common::parse3;
 use common::parse3::{choice, Parser, unsigned_int};
 
@@ -28,48 +27,22 @@ pub fn main(day: &mut Day, input: &[u8]) {
 }
 
 fn parse(data: &[u8]) -> (VecGrid<u8>, Vec<(u8, i8)>) {
-    let mut grid_width = 0;
-    let mut grid_height = 0;
-    for line in parse3::line().iterate(data) {
-        if line.len() == 0 {
-            break;
-        }
-
-        grid_width = max(grid_width, line.len());
-        grid_height += 1;
-    }
-
-    let mut grid = VecGrid::new_with(grid_width, grid_height, b' ');
-    let mut p = Point(0, 0);
-    for ch in data {
-        match *ch {
-            b'\n' => {
-                p.1 += 1;
-                if p.1 == grid_height {
-                    break;
-                }
-                p.0 = 0;
-            }
-            b'#' | b'.' => {
-                *grid.get_mut(&p).unwrap() = *ch;
-                p.0 += 1;
-            }
-            _ => { p.0 += 1 }
-        }
-    }
-
-    let (_, pos) = b"\n\n".first_parsable(data).unwrap();
-    let instructions = unsigned_int::<u8>()
-        .and(choice([
-            b'L'.map_to_value(TURN_LEFT),
-            b'R'.map_to_value(TURN_RIGHT),
-            b'\n'.map_to_value(NO_TURN),
-        ]))
-        .repeat()
-        .parse(&data[pos + 2..])
-        .unwrap();
-
-    (grid, instructions)
+    parse3::grid(|b| match b {
+        b'#' | b'.' => b,
+        _ => b' ',
+    })
+        .padded(b' ')
+        .and(
+            unsigned_int::<u8>()
+                .and(choice([
+                    b'L'.map_to_value(TURN_LEFT),
+                    b'R'.map_to_value(TURN_RIGHT),
+                    b'\n'.map_to_value(NO_TURN),
+                ]))
+                .repeat()
+        )
+        .parse(data)
+        .unwrap()
 }
 
 fn part1<G>(grid: &G, instructions: &[(u8, i8)]) -> usize where G: GetterMutGrid<u8> + FixedGrid {
@@ -110,8 +83,7 @@ fn part1<G>(grid: &G, instructions: &[(u8, i8)]) -> usize where G: GetterMutGrid
 
 fn part2<G>(grid: &G, side_len: usize, instructions: &[(u8, i8)]) -> usize where G: GetterGrid<u8> + FixedGrid {
     let mut cube = Cube::new(grid, side_len);
-    cube.connect_adjacent_faces();
-    cube.fold_once();
+    cube.fold(&CubeNet::fold(grid, side_len, &b' ').unwrap());
 
     let (pos, dir) = cube.run(0, Point(0, 0), instructions);
     password(&pos, dir)
@@ -162,105 +134,23 @@ impl<'i, G> Cube<'i, G> where G: GetterGrid<u8> + FixedGrid {
         }
     }
 
-    fn connect_adjacent_faces(&mut self) {
-        const DIRECTIONS: &[usize; 4] = &[3, 2, 0, 1];
-
-        for i in 0..6 {
-            let cardinals = self.faces[i].index.cardinals_wrapping(3, 3);
-
-            for (j, adj_index) in cardinals.into_iter().enumerate() {
-                if let Some(index) = self.faces.iter().position(|v| v.index == adj_index) {
-                    self.faces[i].neighbors[DIRECTIONS[j]] = (index as u8, DIRECTIONS[j] as i8)
-                }
-            }
-        }
-    }
-
-    fn fold_once(&mut self) -> bool {
-        const TABLE: &[(usize, &[usize], i8)] = &[
-            (0, &[1, 0], DOWN), // right = down right
-            (0, &[3, 0], UP), // right = up right
-            (0, &[2, 2, 2], RIGHT), // right = left left left
-            (0, &[1, 1, 0], LEFT), // right = down down right
-            (0, &[3, 3, 0], LEFT), // right = up up right
-            (0, &[2, 3, 3], LEFT), // right = left up up
-            (0, &[2, 1, 1], LEFT), // right = left down down
-            (1, &[0, 1], RIGHT), // down = right down
-            (1, &[2, 1], LEFT), // down = left down
-            (1, &[3, 3, 3], DOWN), // down = up up up
-            (1, &[0, 0, 1], UP), // down = right right down
-            (1, &[2, 2, 1], UP), // down = left left down
-            (1, &[3, 2, 2], UP), // down = up left left
-            (1, &[3, 0, 3, 3, 0], DOWN), // down = up right up up right
-            (1, &[2, 3, 2, 2], RIGHT), // down = up right up up right
-            (2, &[1, 2], DOWN), // left = down left
-            (2, &[3, 2], UP), // left = up left
-            (2, &[0, 0, 0], RIGHT), // left = right right right
-            (2, &[1, 1, 2], RIGHT), // left = down down left
-            (2, &[0, 3, 3], RIGHT), // left = right up up
-            (2, &[0, 0, 1, 0], UP), // left = right right down right
-            (2, &[3, 0, 3, 3], DOWN), // left = right right down right
-            (3, &[0, 3], RIGHT), // up = right up
-            (3, &[2, 3], LEFT), // up = left up
-            (3, &[1, 2, 2], DOWN), // up = down left left
-            (3, &[1, 0, 0], DOWN), // up = down right right
-            (3, &[0, 0, 3], DOWN), // up = right right up
-            (3, &[2, 2, 3], DOWN), // up = left left up
-            (3, &[1, 0, 0], UP), // up = down down down
-            (3, &[2, 1, 1, 2, 1], UP), // up = left down down left down
-            (3, &[1, 1, 2, 1], RIGHT), // down = down down left down
-        ];
-
-        let mut any_folded = false;
-        let mut faces = self.faces.clone();
-        for i in 0..6 {
-            'fold_table_loop: for (ni, list, mut dir) in TABLE.iter().cloned() {
-                if faces[i].neighbors[ni].0 != 6 {
-                    continue; // Already folded
-                }
-
-                let mut curr = i;
-                for index in list {
-                    let (n, d) = self.faces[curr].neighbors[*index];
-                    if n == 6 {
-                        continue 'fold_table_loop; // Cannot fold this way
-                    }
-
-                    let diff = d - (*index as i8);
-                    dir = (4 - diff + dir) % 4;
-
-                    curr = n as usize;
-                }
-
-                #[cfg(test)] println!("[{}].[{}] with {:?} = {}", i, ni, list, curr);
-
-                any_folded = true;
-                faces[i].neighbors[ni] = (curr as u8, dir);
+    fn fold(&mut self, net: &CubeNet) {
+        for (i, face) in self.faces.iter_mut().enumerate() {
+            for edge in 0..4 {
+                let wrap = net.wrap(i, edge);
+                face.neighbors[edge as usize] = (wrap.face as u8, wrap.dir as i8);
             }
         }
-        self.faces = faces;
-
-        any_folded
     }
 
     fn new(grid: &'i G, side_len: usize) -> Self {
-        let mut faces: ArrayVec<_, 6> = ArrayVec::new();
-        for y in range_step(0, grid.height(), side_len) {
-            for x in range_step(0, grid.width(), side_len) {
-                if *grid.get(&Point(x, y)).unwrap() == b' ' {
-                    continue;
-                }
-
-                faces.push(CubeFace {
-                    index: Point(x / side_len, y / side_len),
-                    grid: SubGrid::new(
-                        grid, Point(x, y),
-                        side_len, side_len,
-                    ),
-                    neighbors: [(NO_NEIGHBOR, -1); 4],
-                });
-            }
-        }
+        let faces = grid.tiles(side_len, side_len).void(&b' ')
+            .map(|(index, grid)| CubeFace {
+                index,
+                grid,
+                neighbors: [(NO_NEIGHBOR, -1); 4],
+            })
+            .collect();
 
         Self { faces }
     }
@@ -527,35 +417,16 @@ mod tests {
     }
 
     #[test]
-    fn cube_connects_adjacent_faces() {
-        const BLANK: (u8, i8) = (NO_NEIGHBOR, -1);
-
+    fn cube_fold_connects_faces() {
         let (grid, _) = parse(P1_EXAMPLE);
         let mut cube = Cube::new(&grid, 4);
-        cube.connect_adjacent_faces();
-
-        assert_eq!(cube.faces[0].neighbors, [BLANK, (3, DOWN), BLANK, BLANK]);
-        assert_eq!(cube.faces[1].neighbors, [(2, RIGHT), BLANK, BLANK, BLANK]);
-        assert_eq!(cube.faces[2].neighbors, [(3, RIGHT), BLANK, (1, LEFT), BLANK]);
-        assert_eq!(cube.faces[3].neighbors, [BLANK, (4, DOWN), (2, LEFT), (0, UP)]);
-        assert_eq!(cube.faces[4].neighbors, [(5, RIGHT), BLANK, BLANK, (3, UP)]);
-        assert_eq!(cube.faces[5].neighbors, [BLANK, BLANK, (4, LEFT), BLANK]);
-    }
+        cube.fold(&CubeNet::fold(&grid, 4, &b' ').unwrap());
 
-    #[test]
-    fn cube_fold_once_does_it_right() {
-        let (grid, _) = parse(P1_EXAMPLE);
-        let mut cube = Cube::new(&grid, 4);
-        cube.connect_adjacent_faces();
-
-        assert_eq!(cube.fold_once(), true);
         assert_eq!(cube.faces[0].neighbors, [(5, LEFT), (3, DOWN), (2, DOWN), (1, DOWN)], "0");
         assert_eq!(cube.faces[1].neighbors, [(2, RIGHT), (4, UP), (5, UP), (0, DOWN)], "1");
         assert_eq!(cube.faces[2].neighbors, [(3, RIGHT), (4, RIGHT), (1, LEFT), (0, RIGHT)], "2");
         assert_eq!(cube.faces[3].neighbors, [(5, DOWN), (4, DOWN), (2, LEFT), (0, UP)], "3");
         assert_eq!(cube.faces[4].neighbors, [(5, RIGHT), (1, UP), (2, UP), (3, UP)], "4");
         assert_eq!(cube.faces[5].neighbors, [(0, LEFT), (1, RIGHT), (4, LEFT), (3, LEFT)], "5");
-
-        assert_eq!(cube.fold_once(), false);
     }
-}
\ No newline at end of file
+}